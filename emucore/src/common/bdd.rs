@@ -0,0 +1,347 @@
+use crate::common::{
+    read::{multi::MultiRead, single::SingleRead},
+    rng::SplitMix64,
+};
+use arrayvec::ArrayVec;
+use core::array;
+use thiserror::Error;
+
+/// The number of entries in the operation cache.
+const CACHE_SIZE: usize = 512;
+
+/// The variable index given to the two terminal nodes, ordering them after every variable.
+const TERMINAL_VAR: u16 = u16::MAX;
+
+#[derive(Clone, Copy, Debug, Eq, Error, Hash, PartialEq)]
+pub enum BddError {
+    #[error("cannot create BDD node as the node table is full")]
+    NodesFull,
+
+    #[error("cannot create BDD variable as every variable index is in use")]
+    VarsFull,
+}
+
+/// A reference to a boolean function stored in a [`Bdd`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BddRef(u32);
+
+impl BddRef {
+    pub const FALSE: Self = Self(0);
+    pub const TRUE: Self = Self(1);
+
+    #[must_use]
+    pub const fn from_bool(value: bool) -> Self {
+        if value { Self::TRUE } else { Self::FALSE }
+    }
+
+    #[must_use]
+    pub const fn is_const(self) -> bool {
+        self.0 <= 1
+    }
+
+    /// Whether some assignment of the variables makes this function true, such as whether a
+    /// branch with this condition can be taken.
+    #[must_use]
+    pub const fn is_satisfiable(self) -> bool {
+        self.0 != Self::FALSE.0
+    }
+
+    /// Projects the function back onto the per-bit mode.
+    #[must_use]
+    pub const fn to_read(self) -> SingleRead {
+        match self {
+            Self::FALSE => SingleRead::Low,
+            Self::TRUE => SingleRead::High,
+            _ => SingleRead::Unknown,
+        }
+    }
+
+    #[must_use]
+    pub fn to_multi_read<const SIZE: usize>(fs: &[Self; SIZE]) -> MultiRead<SIZE> {
+        array::from_fn(|bit| fs[bit].to_read()).into()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct Node {
+    var: u16,
+    low: BddRef,
+    high: BddRef,
+}
+
+/// A reduced ordered binary decision diagram with room for `NODES` nodes.
+///
+/// Every function built in the same diagram shares its nodes, so two references are equal exactly
+/// when their functions are. This lets Unknown bits stay correlated: `x XOR x` is always false,
+/// where the per-bit [`SingleRead`] mode can only say Unknown.
+#[derive(Clone, Debug)]
+pub struct Bdd<const NODES: usize> {
+    nodes: ArrayVec<Node, NODES>,
+    unique: [Option<BddRef>; NODES],
+    cache: [Option<([BddRef; 3], BddRef)>; CACHE_SIZE],
+    vars: u16,
+}
+
+impl<const NODES: usize> Default for Bdd<NODES> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const NODES: usize> Bdd<NODES> {
+    #[must_use]
+    pub fn new() -> Self {
+        let terminal = |value| Node {
+            var: TERMINAL_VAR,
+            low: BddRef::from_bool(value),
+            high: BddRef::from_bool(value),
+        };
+
+        Self {
+            nodes: [terminal(false), terminal(true)].into_iter().collect(),
+            unique: [None; NODES],
+            cache: [None; CACHE_SIZE],
+            vars: 0,
+        }
+    }
+
+    /// The number of nodes in use, including the two terminals.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the diagram holds nothing but the two terminals.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.nodes.len() == 2
+    }
+
+    fn node(&self, f: BddRef) -> Node {
+        self.nodes[f.0 as usize]
+    }
+
+    fn mk(&mut self, var: u16, low: BddRef, high: BddRef) -> Result<BddRef, BddError> {
+        if low == high {
+            return Ok(low);
+        }
+
+        let node = Node { var, low, high };
+        let hash = (usize::from(var).wrapping_mul(0x9e37_79b9)
+            ^ (low.0 as usize).wrapping_mul(0x85eb_ca6b)
+            ^ (high.0 as usize).wrapping_mul(0xc2b2_ae35))
+            % NODES;
+
+        for probe in 0..NODES {
+            let slot = &mut self.unique[(hash + probe) % NODES];
+
+            match *slot {
+                Some(f) if self.nodes[f.0 as usize] == node => return Ok(f),
+                Some(_) => (),
+                None => {
+                    let f =
+                        BddRef(u32::try_from(self.nodes.len()).map_err(|_| BddError::NodesFull)?);
+                    self.nodes.try_push(node).map_err(|_| BddError::NodesFull)?;
+                    *slot = Some(f);
+                    return Ok(f);
+                }
+            }
+        }
+
+        Err(BddError::NodesFull)
+    }
+
+    /// The number of variable indices in use or reserved.
+    #[must_use]
+    pub const fn vars(&self) -> u16 {
+        self.vars
+    }
+
+    /// Keeps the first `count` variable indices for [`Bdd::var`], so fresh variables come after
+    /// them.
+    pub fn reserve_vars(&mut self, count: u16) -> Result<(), BddError> {
+        if count == TERMINAL_VAR {
+            return Err(BddError::VarsFull);
+        }
+
+        self.vars = self.vars.max(count);
+        Ok(())
+    }
+
+    /// The function which is true exactly when variable `index` is.
+    pub fn var(&mut self, index: u16) -> Result<BddRef, BddError> {
+        if index == TERMINAL_VAR {
+            return Err(BddError::VarsFull);
+        }
+
+        let f = self.mk(index, BddRef::FALSE, BddRef::TRUE)?;
+        self.vars = self.vars.max(index + 1);
+        Ok(f)
+    }
+
+    /// Creates a variable which has not been used before.
+    pub fn fresh_var(&mut self) -> Result<BddRef, BddError> {
+        self.var(self.vars)
+    }
+
+    fn cache_slot([f, g, h]: [BddRef; 3]) -> usize {
+        let mix = |x: u64| SplitMix64::new(x).next_u64();
+        let hash = mix(mix(mix(u64::from(f.0)) ^ u64::from(g.0)) ^ u64::from(h.0));
+
+        usize::try_from(hash % CACHE_SIZE as u64).unwrap_or(0)
+    }
+
+    /// If `f` then `g` else `h`.
+    pub fn ite(&mut self, f: BddRef, g: BddRef, h: BddRef) -> Result<BddRef, BddError> {
+        match (f, g, h) {
+            (BddRef::TRUE, _, _) => return Ok(g),
+            (BddRef::FALSE, _, _) => return Ok(h),
+            _ if g == h => return Ok(g),
+            (_, BddRef::TRUE, BddRef::FALSE) => return Ok(f),
+            _ => (),
+        }
+
+        let key = [f, g, h];
+        let slot = Self::cache_slot(key);
+        if let Some((cached, res)) = self.cache[slot]
+            && cached == key
+        {
+            return Ok(res);
+        }
+
+        let var = key
+            .iter()
+            .map(|&f| self.node(f).var)
+            .min()
+            .unwrap_or(TERMINAL_VAR);
+        let cofactors = key.map(|f| {
+            let node = self.node(f);
+            if node.var == var {
+                (node.low, node.high)
+            } else {
+                (f, f)
+            }
+        });
+
+        let [(f0, f1), (g0, g1), (h0, h1)] = cofactors;
+        let low = self.ite(f0, g0, h0)?;
+        let high = self.ite(f1, g1, h1)?;
+        let res = self.mk(var, low, high)?;
+
+        self.cache[slot] = Some((key, res));
+        Ok(res)
+    }
+
+    pub fn not(&mut self, f: BddRef) -> Result<BddRef, BddError> {
+        self.ite(f, BddRef::FALSE, BddRef::TRUE)
+    }
+
+    pub fn and(&mut self, f: BddRef, g: BddRef) -> Result<BddRef, BddError> {
+        self.ite(f, g, BddRef::FALSE)
+    }
+
+    pub fn or(&mut self, f: BddRef, g: BddRef) -> Result<BddRef, BddError> {
+        self.ite(f, BddRef::TRUE, g)
+    }
+
+    pub fn xor(&mut self, f: BddRef, g: BddRef) -> Result<BddRef, BddError> {
+        let not_g = self.not(g)?;
+        self.ite(f, not_g, g)
+    }
+
+    /// The symbolic counterpart of [`Combine::mux`](crate::common::combine::Combine::mux).
+    pub fn mux(&mut self, cond: BddRef, low: BddRef, high: BddRef) -> Result<BddRef, BddError> {
+        self.ite(cond, high, low)
+    }
+
+    /// The symbolic counterpart of
+    /// [`Combine::combine_with`](crate::common::combine::Combine::combine_with), which picks
+    /// between `f` and `g` with a fresh variable.
+    pub fn combine(&mut self, f: BddRef, g: BddRef) -> Result<BddRef, BddError> {
+        if f == g {
+            return Ok(f);
+        }
+
+        let choice = self.fresh_var()?;
+        self.ite(choice, g, f)
+    }
+
+    /// Lifts a read into the diagram, giving each Unknown a fresh variable.
+    pub fn lift(&mut self, read: SingleRead) -> Result<BddRef, BddError> {
+        read.as_bool()
+            .map_or_else(|| self.fresh_var(), |value| Ok(BddRef::from_bool(value)))
+    }
+
+    pub fn lift_multi<const SIZE: usize>(
+        &mut self,
+        reads: &MultiRead<SIZE>,
+    ) -> Result<[BddRef; SIZE], BddError> {
+        let mut res = [BddRef::FALSE; SIZE];
+        for (f, &read) in res.iter_mut().zip(reads.iter()) {
+            *f = self.lift(read)?;
+        }
+        Ok(res)
+    }
+
+    /// Iterates over an assignment of variables which makes `f` true, or returns `None` if there
+    /// is no such assignment. Variables which are not listed can take either value.
+    #[must_use]
+    pub fn any_sat(&self, f: BddRef) -> Option<impl Iterator<Item = (u16, bool)> + '_> {
+        f.is_satisfiable().then(|| {
+            let mut f = f;
+            core::iter::from_fn(move || {
+                if f.is_const() {
+                    return None;
+                }
+
+                let node = self.node(f);
+                let value = node.low == BddRef::FALSE;
+                f = if value { node.high } else { node.low };
+                Some((node.var, value))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_correlations() {
+        let mut bdd = Bdd::<64>::new();
+        let x = bdd.lift(SingleRead::Unknown).unwrap();
+
+        assert_eq!(bdd.xor(x, x).unwrap(), BddRef::FALSE);
+        let not_x = bdd.not(x).unwrap();
+        assert_eq!(bdd.or(x, not_x).unwrap(), BddRef::TRUE);
+        assert_eq!(x.to_read(), SingleRead::Unknown);
+    }
+
+    #[test]
+    fn finds_satisfying_assignment() {
+        let mut bdd = Bdd::<64>::new();
+        let [a, b, c] = bdd.lift_multi(&[SingleRead::Unknown; 3].into()).unwrap();
+
+        let a_and_not_b = {
+            let not_b = bdd.not(b).unwrap();
+            bdd.and(a, not_b).unwrap()
+        };
+        let f = bdd.mux(c, BddRef::FALSE, a_and_not_b).unwrap();
+        let assignment: ArrayVec<_, 3> = bdd.any_sat(f).unwrap().collect();
+        assert_eq!(assignment.as_slice(), [(0, true), (1, false), (2, true)]);
+
+        let not_a = bdd.not(a).unwrap();
+        let contradiction = bdd.and(f, not_a).unwrap();
+        assert!(bdd.any_sat(contradiction).is_none());
+    }
+
+    #[test]
+    fn reports_full_table() {
+        let mut bdd = Bdd::<4>::new();
+        bdd.fresh_var().unwrap();
+        bdd.fresh_var().unwrap();
+        assert_eq!(bdd.fresh_var(), Err(BddError::NodesFull));
+        assert_eq!(bdd.vars(), 2);
+    }
+}
@@ -0,0 +1,258 @@
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Eq, Error, Hash, PartialEq)]
+pub enum CodecError {
+    #[error("cannot encode data as the buffer is full")]
+    BufferFull,
+
+    #[error("cannot decode data as it ends unexpectedly")]
+    UnexpectedEnd,
+
+    #[error("cannot decode data as it contains an invalid value")]
+    InvalidValue,
+
+    #[error("cannot decode data as it continues past its expected end")]
+    TrailingBytes,
+}
+
+pub trait Encode {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError>;
+}
+
+pub trait Decode: Sized {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError>;
+}
+
+/// An upper bound on the number of bytes a value of this type encodes to.
+pub trait MaxEncodedLen {
+    const MAX_ENCODED_LEN: usize;
+}
+
+/// The most bytes a varint holding a `bits`-bit value can take.
+#[must_use]
+pub const fn varint_max_len(bits: u32) -> usize {
+    bits.div_ceil(7) as usize
+}
+
+/// The most bytes a section holding a payload of at most `payload` bytes can take.
+#[must_use]
+pub const fn section_max_len(payload: usize) -> usize {
+    1 + 2 + payload
+}
+
+/// The sum of the longest encodings of the given types.
+macro_rules! max_encoded_len {
+    ($($ty:ty),+ $(,)?) => {
+        0 $(+ <$ty as $crate::common::codec::MaxEncodedLen>::MAX_ENCODED_LEN)+
+    };
+}
+
+pub(crate) use max_encoded_len;
+
+pub struct Writer<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> Writer<'a> {
+    pub const fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    #[must_use]
+    pub const fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Moves back to `position`, discarding everything written after it.
+    pub const fn truncate(&mut self, position: usize) {
+        if position < self.pos {
+            self.pos = position;
+        }
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), CodecError> {
+        let end = self.pos + bytes.len();
+        self.buf
+            .get_mut(self.pos..end)
+            .ok_or(CodecError::BufferFull)?
+            .copy_from_slice(bytes);
+        self.pos = end;
+        Ok(())
+    }
+
+    /// Writes a section of data prefixed by its tag and length, so decoders can skip sections
+    /// they do not recognise.
+    pub fn write_section(
+        &mut self,
+        tag: u8,
+        payload: impl FnOnce(&mut Self) -> Result<(), CodecError>,
+    ) -> Result<(), CodecError> {
+        self.write_u8(tag)?;
+        let len_pos = self.pos;
+        self.write_u16(0)?;
+
+        payload(self)?;
+
+        let len = u16::try_from(self.pos - len_pos - 2).map_err(|_| CodecError::InvalidValue)?;
+        self.buf[len_pos..len_pos + 2].copy_from_slice(&len.to_le_bytes());
+        Ok(())
+    }
+
+    pub fn write_u8(&mut self, value: u8) -> Result<(), CodecError> {
+        self.write_bytes(&[value])
+    }
+
+    pub fn write_u16(&mut self, value: u16) -> Result<(), CodecError> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_u32(&mut self, value: u32) -> Result<(), CodecError> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_u64(&mut self, value: u64) -> Result<(), CodecError> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    /// Writes an unsigned LEB128 integer, which takes a single byte for values below 128.
+    pub fn write_varint(&mut self, mut value: u64) -> Result<(), CodecError> {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+
+            if value == 0 {
+                return self.write_u8(byte);
+            }
+
+            self.write_u8(byte | 0x80)?;
+        }
+    }
+}
+
+pub struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    #[must_use]
+    pub const fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    #[must_use]
+    pub const fn position(&self) -> usize {
+        self.pos
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    pub fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N], CodecError> {
+        let end = self.pos + N;
+        let bytes = self
+            .buf
+            .get(self.pos..end)
+            .ok_or(CodecError::UnexpectedEnd)?
+            .try_into()
+            .map_err(|_| CodecError::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    pub fn read_slice(&mut self, len: usize) -> Result<&'a [u8], CodecError> {
+        let end = self.pos + len;
+        let slice = self
+            .buf
+            .get(self.pos..end)
+            .ok_or(CodecError::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    /// Checks that every byte has been read.
+    pub const fn finish(&self) -> Result<(), CodecError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(CodecError::TrailingBytes)
+        }
+    }
+
+    /// Reads a section written by [`Writer::write_section`], returning its tag and payload.
+    pub fn read_section(&mut self) -> Result<(u8, Self), CodecError> {
+        let tag = self.read_u8()?;
+        let len = self.read_u16()?;
+        Ok((tag, Self::new(self.read_slice(usize::from(len))?)))
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, CodecError> {
+        self.read_bytes::<1>().map(|[byte]| byte)
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, CodecError> {
+        self.read_bytes().map(u16::from_le_bytes)
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, CodecError> {
+        self.read_bytes().map(u32::from_le_bytes)
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, CodecError> {
+        self.read_bytes().map(u64::from_le_bytes)
+    }
+
+    pub fn read_varint(&mut self) -> Result<u64, CodecError> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            value |= u64::from(byte & 0x7f) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(CodecError::InvalidValue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, 1)]
+    #[case(127, 1)]
+    #[case(128, 2)]
+    #[case(19_912, 3)]
+    #[case(u64::MAX, 10)]
+    fn varint_round_trip(#[case] value: u64, #[case] len: usize) {
+        let mut buf = [0; 10];
+        let mut w = Writer::new(&mut buf);
+        w.write_varint(value).unwrap();
+        assert_eq!(w.position(), len);
+
+        let mut r = Reader::new(&buf[..len]);
+        assert_eq!(r.read_varint().unwrap(), value);
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn write_past_end() {
+        let mut buf = [0; 1];
+        let mut w = Writer::new(&mut buf);
+        assert_eq!(w.write_u16(0x1234), Err(CodecError::BufferFull));
+    }
+
+    #[test]
+    fn read_past_end() {
+        let mut r = Reader::new(&[0x34]);
+        assert_eq!(r.read_u16(), Err(CodecError::UnexpectedEnd));
+    }
+}
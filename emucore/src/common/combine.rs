@@ -4,6 +4,14 @@ pub trait Combine {
     #[must_use]
     fn combine_with(&self, other: &Self) -> Self;
 
+    /// Whether every state `other` could be in is also a state `self` could be in.
+    fn covers(&self, other: &Self) -> bool
+    where
+        Self: PartialEq + Sized,
+    {
+        self.combine_with(other) == *self
+    }
+
     fn mux<L, H>(cond: BaseCondition, low_opt: L, high_opt: H) -> Self
     where
         Self: Sized,
@@ -1,11 +1,158 @@
-use crate::common::line::ident::LineIdent;
+use crate::common::line::{ident::LineIdent, single::DriveState};
+use arrayvec::ArrayVec;
+use core::fmt;
+use strum_macros::Display;
 use thiserror::Error;
 
+/// The most drivers a single line can have.
+pub const MAX_LINE_DRIVERS: usize = 4;
+
+/// A component which can drive a line.
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum LineDriver {
+    #[strum(to_string = "CPU")]
+    Cpu,
+
+    #[strum(to_string = "RIOT")]
+    Riot,
+
+    #[strum(to_string = "TIA")]
+    Tia,
+
+    #[strum(to_string = "cartridge")]
+    Cartridge,
+
+    #[strum(to_string = "external")]
+    External,
+}
+
+/// How one component was driving a line.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DriverState {
+    pub driver: LineDriver,
+    pub drive: DriveState,
+}
+
+impl fmt::Display for DriverState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.driver, self.drive)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum ClockEdge {
+    #[strum(to_string = "rising edge")]
+    Rising,
+
+    #[strum(to_string = "falling edge")]
+    Falling,
+}
+
+/// Where and when a line error happened, as far as the code raising it knows.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct LineContext {
+    pub drivers: ArrayVec<DriverState, MAX_LINE_DRIVERS>,
+    pub edge: Option<ClockEdge>,
+    pub cycle: Option<u64>,
+}
+
+impl fmt::Display for LineContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(cycle) = self.cycle {
+            write!(f, " on cycle {cycle}")?;
+        }
+
+        if let Some(edge) = self.edge {
+            write!(f, " before the {edge}")?;
+        }
+
+        for (i, driver) in self.drivers.iter().enumerate() {
+            let sep = if i == 0 { " with drivers " } else { ", " };
+            write!(f, "{sep}{driver}")?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Eq, Error, Hash, PartialEq)]
 pub enum LineError {
-    #[error("cannot perform operation on line {ident} without causing a short circuit")]
-    ShortCircuit { ident: LineIdent },
+    #[error("cannot perform operation on line {ident} without causing a short circuit{context}")]
+    ShortCircuit {
+        ident: LineIdent,
+        context: LineContext,
+    },
+
+    #[error("cannot read line {ident} as it has no possible signal through it{context}")]
+    ImpossibleLineSignal {
+        ident: LineIdent,
+        context: LineContext,
+    },
+}
+
+impl LineError {
+    #[must_use]
+    pub const fn ident(&self) -> LineIdent {
+        match self {
+            Self::ShortCircuit { ident, .. } | Self::ImpossibleLineSignal { ident, .. } => *ident,
+        }
+    }
+
+    #[must_use]
+    pub const fn context(&self) -> &LineContext {
+        match self {
+            Self::ShortCircuit { context, .. } | Self::ImpossibleLineSignal { context, .. } => {
+                context
+            }
+        }
+    }
+
+    /// Replaces the context of the error.
+    #[must_use]
+    pub fn with_context(mut self, new_context: LineContext) -> Self {
+        match &mut self {
+            Self::ShortCircuit { context, .. } | Self::ImpossibleLineSignal { context, .. } => {
+                *context = new_context;
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::signal::LineSignal;
+
+    #[test]
+    fn names_drivers() {
+        let err = LineError::ShortCircuit {
+            ident: "rw".into(),
+            context: LineContext::default(),
+        }
+        .with_context(LineContext {
+            drivers: [
+                DriverState {
+                    driver: LineDriver::Cpu,
+                    drive: LineSignal::Low.into(),
+                },
+                DriverState {
+                    driver: LineDriver::External,
+                    drive: LineSignal::High.into(),
+                },
+            ]
+            .into_iter()
+            .collect(),
+            edge: Some(ClockEdge::Falling),
+            cycle: Some(7),
+        });
 
-    #[error("cannot read line {ident} as it has no possible signal through it")]
-    ImpossibleLineSignal { ident: LineIdent },
+        let mut buf = arrayvec::ArrayString::<160>::new();
+        fmt::write(&mut buf, format_args!("{err}")).unwrap();
+        assert_eq!(
+            buf.as_str(),
+            "cannot perform operation on line rw without causing a short circuit on cycle 7 \
+             before the falling edge with drivers CPU (low), external (high)"
+        );
+    }
 }
@@ -9,6 +9,17 @@ pub enum LineIdent {
     BusLine { bus_name: &'static str, bit: usize },
 }
 
+impl LineIdent {
+    /// The name of the net this line belongs to.
+    #[must_use]
+    pub const fn net_name(self) -> &'static str {
+        match self {
+            Self::UniqueLine { name } => name,
+            Self::BusLine { bus_name, .. } => bus_name,
+        }
+    }
+}
+
 impl From<&'static str> for LineIdent {
     fn from(value: &'static str) -> Self {
         Self::UniqueLine { name: value }
@@ -1,6 +1,11 @@
 use crate::common::{
+    codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer},
     combine::Combine,
-    line::{error::LineError, ident::LineIdent, single::DriveState},
+    line::{
+        error::{LineContext, LineError},
+        ident::LineIdent,
+        single::DriveState,
+    },
     read::{multi::MultiRead, single::SingleRead},
     signal::LineSignal,
 };
@@ -42,6 +47,7 @@ pub fn read_ok(&self, name: &'static str) -> Result<MultiRead<SIZE>, LineError>
                 bus_name: name,
                 bit,
             },
+            context: LineContext::default(),
         })
     }
 
@@ -61,6 +67,7 @@ pub fn contend_ok(states: &[&Self], name: &'static str) -> Result<Self, LineErro
                 bus_name: name,
                 bit,
             },
+            context: LineContext::default(),
         })
     }
 }
@@ -70,3 +77,69 @@ fn combine_with(&self, other: &Self) -> Self {
         array::from_fn(|bit| self[bit].combine_with(&other[bit])).into()
     }
 }
+
+/// A nibble marking a line state which needs weak drive bits, stored in full after its pair.
+const WEAK_ESCAPE: u8 = 0b1000;
+
+/// Packs two line states into each byte.
+impl<const SIZE: usize> Encode for BusDriveState<SIZE> {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        let nibble = |state: &DriveState| state.to_bits().min(WEAK_ESCAPE);
+
+        for pair in self.chunks(2) {
+            let high_nibble = pair.get(1).map_or(0, nibble);
+            w.write_u8(nibble(&pair[0]) | high_nibble << 4)?;
+
+            for state in pair.iter().filter(|&state| nibble(state) == WEAK_ESCAPE) {
+                state.encode(w)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Every state may be a weak drive, which follows its nibble with a full byte.
+impl<const SIZE: usize> MaxEncodedLen for BusDriveState<SIZE> {
+    const MAX_ENCODED_LEN: usize = SIZE.div_ceil(2) + SIZE * DriveState::MAX_ENCODED_LEN;
+}
+
+impl<const SIZE: usize> Decode for BusDriveState<SIZE> {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let mut res: Self = [DriveState::none_enabled(); SIZE].into();
+
+        for pair in res.chunks_mut(2) {
+            let byte = r.read_u8()?;
+
+            for (state, bits) in pair.iter_mut().zip([byte & 0xf, byte >> 4]) {
+                *state = match bits {
+                    WEAK_ESCAPE => Decode::decode(r)?,
+                    ..WEAK_ESCAPE => DriveState::from_bits(bits).ok_or(CodecError::InvalidValue)?,
+                    _ => return Err(CodecError::InvalidValue),
+                };
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_weak_drives() {
+        let mut bus = BusDriveState::<3>::from_value(0b101);
+        bus[1] = LineSignal::WeakHigh.into();
+        bus[2] = bus[2].combine_with(&LineSignal::WeakLow.into());
+
+        let mut buf = [0; 8];
+        let mut w = Writer::new(&mut buf);
+        bus.encode(&mut w).unwrap();
+        let len = w.position();
+
+        assert_eq!(len, 4);
+        assert_eq!(Decode::decode(&mut Reader::new(&buf[..len])), Ok(bus));
+    }
+}
@@ -1,15 +1,24 @@
 use crate::common::{
+    codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer},
     combine::Combine,
-    line::{error::LineError, ident::LineIdent},
+    line::{
+        error::{LineContext, LineError},
+        ident::LineIdent,
+    },
     read::single::SingleRead,
     signal::LineSignal,
 };
+use core::fmt;
 
+/// The set of signals a line could be driven with.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct DriveState {
     pub low: bool,
     pub high: bool,
     pub high_z: bool,
+    pub weak_low: bool,
+    pub weak_high: bool,
 }
 
 impl DriveState {
@@ -19,12 +28,28 @@ pub const fn none_enabled() -> Self {
             low: false,
             high: false,
             high_z: false,
+            weak_low: false,
+            weak_high: false,
+        }
+    }
+
+    /// A switch to ground, which pulls its line low while closed and leaves it floating while
+    /// open. A switch which could be either is `None`.
+    #[must_use]
+    pub const fn ground_switch(closed: Option<bool>) -> Self {
+        Self {
+            low: !matches!(closed, Some(false)),
+            high_z: !matches!(closed, Some(true)),
+            ..Self::none_enabled()
         }
     }
 
     #[must_use]
     pub const fn read(self) -> Option<SingleRead> {
-        match (self.low, self.high, self.high_z) {
+        let low = self.low || self.weak_low;
+        let high = self.high || self.weak_high;
+
+        match (low, high, self.high_z) {
             (false, false, false) => None,
             (true, false, false) => Some(SingleRead::Low),
             (false, true, false) => Some(SingleRead::High),
@@ -32,29 +57,60 @@ pub const fn read(self) -> Option<SingleRead> {
         }
     }
 
+    #[must_use]
+    pub const fn to_bits(self) -> u8 {
+        self.low as u8
+            | (self.high as u8) << 1
+            | (self.high_z as u8) << 2
+            | (self.weak_low as u8) << 3
+            | (self.weak_high as u8) << 4
+    }
+
+    #[must_use]
+    pub const fn from_bits(bits: u8) -> Option<Self> {
+        if bits > 0b1_1111 {
+            return None;
+        }
+
+        Some(Self {
+            low: bits & 1 != 0,
+            high: bits & 0b10 != 0,
+            high_z: bits & 0b100 != 0,
+            weak_low: bits & 0b1000 != 0,
+            weak_high: bits & 0b1_0000 != 0,
+        })
+    }
+
     pub fn read_ok(self, ident: LineIdent) -> Result<SingleRead, LineError> {
-        self.read().ok_or(LineError::ImpossibleLineSignal { ident })
+        self.read().ok_or(LineError::ImpossibleLineSignal {
+            ident,
+            context: LineContext::default(),
+        })
+    }
+
+    pub(crate) fn signals(self) -> impl Iterator<Item = LineSignal> {
+        [
+            (self.low, LineSignal::Low),
+            (self.high, LineSignal::High),
+            (self.high_z, LineSignal::HighZ),
+            (self.weak_low, LineSignal::WeakLow),
+            (self.weak_high, LineSignal::WeakHigh),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, signal)| enabled.then_some(signal))
     }
 
     fn contend_pair(self, other: Self) -> Option<Self> {
         let mut result = Self::none_enabled();
 
-        let iter_possible = |state: Self| {
-            [
-                (state.low, LineSignal::Low),
-                (state.high, LineSignal::High),
-                (state.high_z, LineSignal::HighZ),
-            ]
-            .into_iter()
-            .filter_map(|(enabled, signal)| enabled.then_some(signal))
-        };
-
-        for first_signal in iter_possible(self) {
-            for second_signal in iter_possible(other) {
+        for first_signal in self.signals() {
+            for second_signal in other.signals() {
                 match first_signal.contend_with(second_signal)? {
                     LineSignal::Low => result.low = true,
                     LineSignal::High => result.high = true,
                     LineSignal::HighZ => result.high_z = true,
+                    LineSignal::WeakLow => result.weak_low = true,
+                    LineSignal::WeakHigh => result.weak_high = true,
                 }
             }
         }
@@ -63,11 +119,7 @@ fn contend_pair(self, other: Self) -> Option<Self> {
     }
 
     pub fn contend(mut states: impl Iterator<Item = Self>) -> Option<Self> {
-        let init = Self {
-            low: false,
-            high: false,
-            high_z: true,
-        };
+        let init = LineSignal::HighZ.into();
 
         states.try_fold(init, Self::contend_pair)
     }
@@ -76,7 +128,28 @@ pub fn contend_ok(
         states: impl Iterator<Item = Self>,
         ident: LineIdent,
     ) -> Result<Self, LineError> {
-        Self::contend(states).ok_or(LineError::ShortCircuit { ident })
+        Self::contend(states).ok_or(LineError::ShortCircuit {
+            ident,
+            context: LineContext::default(),
+        })
+    }
+}
+
+/// Lists the possible signals, such as `low or high-impedance`.
+impl fmt::Display for DriveState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut any = false;
+        for signal in self.signals() {
+            let sep = if any { " or " } else { "" };
+            write!(f, "{sep}{signal}")?;
+            any = true;
+        }
+
+        if !any {
+            write!(f, "undriven")?;
+        }
+
+        Ok(())
     }
 }
 
@@ -85,7 +158,7 @@ fn from(value: SingleRead) -> Self {
         Self {
             low: matches!(value, SingleRead::Low | SingleRead::Unknown),
             high: matches!(value, SingleRead::High | SingleRead::Unknown),
-            high_z: false,
+            ..Self::none_enabled()
         }
     }
 }
@@ -96,6 +169,8 @@ fn from(value: LineSignal) -> Self {
             low: matches!(value, LineSignal::Low),
             high: matches!(value, LineSignal::High),
             high_z: matches!(value, LineSignal::HighZ),
+            weak_low: matches!(value, LineSignal::WeakLow),
+            weak_high: matches!(value, LineSignal::WeakHigh),
         }
     }
 }
@@ -105,7 +180,7 @@ fn from(value: bool) -> Self {
         Self {
             low: !value,
             high: value,
-            high_z: false,
+            ..Self::none_enabled()
         }
     }
 }
@@ -116,6 +191,24 @@ fn combine_with(&self, other: &Self) -> Self {
             low: self.low || other.low,
             high: self.high || other.high,
             high_z: self.high_z || other.high_z,
+            weak_low: self.weak_low || other.weak_low,
+            weak_high: self.weak_high || other.weak_high,
         }
     }
 }
+
+impl Encode for DriveState {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        w.write_u8(self.to_bits())
+    }
+}
+
+impl MaxEncodedLen for DriveState {
+    const MAX_ENCODED_LEN: usize = 1;
+}
+
+impl Decode for DriveState {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        Self::from_bits(r.read_u8()?).ok_or(CodecError::InvalidValue)
+    }
+}
@@ -1,6 +1,9 @@
+pub mod bdd;
+pub mod codec;
 pub mod combine;
 pub mod cond;
 pub mod line;
 pub mod read;
 pub mod reg;
+pub mod rng;
 pub mod signal;
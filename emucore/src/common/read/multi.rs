@@ -1,4 +1,5 @@
 use crate::common::{
+    codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer},
     combine::Combine,
     cond::{base::BaseCondition, check::CheckIs},
     read::single::SingleRead,
@@ -16,7 +17,7 @@ pub fn from_value(value: u16) -> Self {
         array::from_fn(|bit| SingleRead::from(value >> bit & 1 == 1)).into()
     }
 
-    pub fn iter_possible_reads(&self) -> impl Iterator<Item = u16> {
+    pub fn iter_possible_reads(&self) -> impl Iterator<Item = u16> + use<SIZE> {
         let mut count = ArrayVec::<_, SIZE>::new();
         let mut mask = 0;
 
@@ -160,3 +161,39 @@ fn is(&self, pattern: &Self) -> BaseCondition {
         }
     }
 }
+
+/// Packs four reads into each byte.
+impl<const SIZE: usize> Encode for MultiRead<SIZE> {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        for chunk in self.chunks(4) {
+            let byte = chunk
+                .iter()
+                .enumerate()
+                .fold(0, |acc, (i, read)| acc | read.to_bits() << (i * 2));
+            w.write_u8(byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<const SIZE: usize> MaxEncodedLen for MultiRead<SIZE> {
+    const MAX_ENCODED_LEN: usize = SIZE.div_ceil(4);
+}
+
+impl<const SIZE: usize> Decode for MultiRead<SIZE> {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let mut res: Self = [SingleRead::Unknown; SIZE].into();
+
+        for chunk in res.chunks_mut(4) {
+            let byte = r.read_u8()?;
+
+            for (i, read) in chunk.iter_mut().enumerate() {
+                *read = SingleRead::from_bits(byte >> (i * 2) & 0b11)
+                    .ok_or(CodecError::InvalidValue)?;
+            }
+        }
+
+        Ok(res)
+    }
+}
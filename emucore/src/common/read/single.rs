@@ -1,4 +1,5 @@
 use crate::common::{
+    codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer},
     combine::Combine,
     cond::{IsCondition, base::BaseCondition},
 };
@@ -39,6 +40,25 @@ pub const fn could_read(self, state: bool) -> bool {
         }
     }
 
+    #[must_use]
+    pub const fn to_bits(self) -> u8 {
+        match self {
+            Self::Low => 0,
+            Self::High => 1,
+            Self::Unknown => 2,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0 => Some(Self::Low),
+            1 => Some(Self::High),
+            2 => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+
     #[must_use]
     pub const fn possible_reads(self) -> &'static [bool] {
         match self {
@@ -74,3 +94,19 @@ fn combine_with(&self, other: &Self) -> Self {
         }
     }
 }
+
+impl Encode for SingleRead {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        w.write_u8(self.to_bits())
+    }
+}
+
+impl MaxEncodedLen for SingleRead {
+    const MAX_ENCODED_LEN: usize = 1;
+}
+
+impl Decode for SingleRead {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        Self::from_bits(r.read_u8()?).ok_or(CodecError::InvalidValue)
+    }
+}
@@ -0,0 +1,51 @@
+/// A small deterministic PRNG (splitmix64), so seeded runs give the same values on every platform.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub const fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Hands out random bits one at a time, drawing a new word only when the last one is used up.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BitSource {
+    rng: SplitMix64,
+    word: u64,
+    left: u32,
+}
+
+impl BitSource {
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            rng: SplitMix64::new(seed),
+            word: 0,
+            left: 0,
+        }
+    }
+
+    pub const fn next_bit(&mut self) -> bool {
+        if self.left == 0 {
+            self.word = self.rng.next_u64();
+            self.left = u64::BITS;
+        }
+
+        let bit = self.word & 1 == 1;
+        self.word >>= 1;
+        self.left -= 1;
+        bit
+    }
+}
@@ -10,25 +10,46 @@ pub enum LineSignal {
 
     #[strum(to_string = "high-impedance")]
     HighZ,
+
+    /// A resistive pull-down, which any strong driver overrides.
+    #[strum(to_string = "weak low")]
+    WeakLow,
+
+    /// A resistive pull-up, which any strong driver overrides.
+    #[strum(to_string = "weak high")]
+    WeakHigh,
 }
 
 impl LineSignal {
     #[must_use]
     pub const fn as_bool(self) -> Option<bool> {
         match self {
-            Self::Low => Some(false),
-            Self::High => Some(true),
+            Self::Low | Self::WeakLow => Some(false),
+            Self::High | Self::WeakHigh => Some(true),
             Self::HighZ => None,
         }
     }
 
+    #[must_use]
+    pub const fn is_weak(self) -> bool {
+        matches!(self, Self::WeakLow | Self::WeakHigh)
+    }
+
+    /// Resolves two drivers on the same line, or returns `None` if they fight at equal strength.
     #[must_use]
     pub const fn contend_with(self, other: Self) -> Option<Self> {
         match (self, other) {
             (Self::Low, Self::Low) => Some(Self::Low),
             (Self::High, Self::High) => Some(Self::High),
+            (Self::WeakLow, Self::WeakLow) => Some(Self::WeakLow),
+            (Self::WeakHigh, Self::WeakHigh) => Some(Self::WeakHigh),
             (any, Self::HighZ) | (Self::HighZ, any) => Some(any),
-            (Self::Low, Self::High) | (Self::High, Self::Low) => None,
+            (strong @ (Self::Low | Self::High), Self::WeakLow | Self::WeakHigh)
+            | (Self::WeakLow | Self::WeakHigh, strong @ (Self::Low | Self::High)) => Some(strong),
+            (Self::Low, Self::High)
+            | (Self::High, Self::Low)
+            | (Self::WeakLow, Self::WeakHigh)
+            | (Self::WeakHigh, Self::WeakLow) => None,
         }
     }
 }
@@ -55,6 +76,8 @@ fn from_bool(#[case] b: bool, #[case] signal: LineSignal) {
     #[case(LineSignal::Low, Some(false))]
     #[case(LineSignal::High, Some(true))]
     #[case(LineSignal::HighZ, None)]
+    #[case(LineSignal::WeakLow, Some(false))]
+    #[case(LineSignal::WeakHigh, Some(true))]
     fn as_bool(#[case] signal: LineSignal, #[case] b: Option<bool>) {
         assert_eq!(signal.as_bool(), b);
     }
@@ -67,6 +90,10 @@ fn as_bool(#[case] signal: LineSignal, #[case] b: Option<bool>) {
     #[case(LineSignal::HighZ, LineSignal::Low, LineSignal::Low)]
     #[case(LineSignal::HighZ, LineSignal::High, LineSignal::High)]
     #[case(LineSignal::HighZ, LineSignal::HighZ, LineSignal::HighZ)]
+    #[case(LineSignal::WeakHigh, LineSignal::Low, LineSignal::Low)]
+    #[case(LineSignal::High, LineSignal::WeakLow, LineSignal::High)]
+    #[case(LineSignal::WeakHigh, LineSignal::HighZ, LineSignal::WeakHigh)]
+    #[case(LineSignal::WeakLow, LineSignal::WeakLow, LineSignal::WeakLow)]
     fn contend_together_success(
         #[case] first: LineSignal,
         #[case] second: LineSignal,
@@ -78,6 +105,7 @@ fn contend_together_success(
     #[rstest]
     #[case(LineSignal::Low, LineSignal::High)]
     #[case(LineSignal::High, LineSignal::Low)]
+    #[case(LineSignal::WeakLow, LineSignal::WeakHigh)]
     fn contend_together_failure(#[case] first: LineSignal, #[case] second: LineSignal) {
         assert!(first.contend_with(second).is_none());
     }
@@ -3,8 +3,9 @@
 
 use crate::{
     common::{
-        combine::mux_matches,
-        cond::{base::BaseCondition, check::CheckIs},
+        codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer, max_encoded_len},
+        combine::{Combine, mux_matches},
+        cond::check::CheckIs,
         line::{multi::BusDriveState, single::DriveState},
         read::single::SingleRead,
         signal::LineSignal,
@@ -18,7 +19,7 @@
 
 macro_rules! ic {
     ($r:ident, $($v:literal),+) => {
-        ($(BaseCondition::from($r.reg.instr_cycle[$v]))|+)
+        ($($r.reg.instr_cycle.is($v))|+)
     };
 }
 
@@ -48,8 +49,26 @@ pub fn new() -> Self {
         }
     }
 
+    #[must_use]
+    pub const fn regs(&self) -> &CpuRegs {
+        &self.reg
+    }
+
+    pub const fn regs_mut(&mut self) -> &mut CpuRegs {
+        &mut self.reg
+    }
+
+    /// Every output drive which reaches a net, in the order address bus, data bus, then R/W.
+    pub fn drives_mut(&mut self) -> impl Iterator<Item = &mut DriveState> {
+        self.a_out
+            .iter_mut()
+            .chain(self.db_out.iter_mut())
+            .chain([&mut self.rw_out])
+    }
+
     pub fn handle_rising_edge(&mut self, line_reads: CpuLineReads) {
-        let r = CpuAllReads::new(line_reads, self.reg.clone());
+        let _r = CpuAllReads::new(line_reads, self.reg.clone());
+        self.phi2_out = LineSignal::High.into();
         todo!()
     }
 
@@ -72,7 +91,52 @@ fn update_s(&mut self, r: &CpuAllReads) {
 
     pub fn handle_falling_edge(&mut self, line_reads: CpuLineReads) {
         let r = CpuAllReads::new(line_reads, self.reg.clone());
+        self.phi2_out = LineSignal::Low.into();
         self.update_s(&r);
         todo!()
     }
 }
+
+impl Encode for Cpu {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        self.phi2_out.encode(w)?;
+        self.a_out.encode(w)?;
+        self.db_out.encode(w)?;
+        self.rw_out.encode(w)?;
+        self.reg.encode(w)
+    }
+}
+
+impl MaxEncodedLen for Cpu {
+    const MAX_ENCODED_LEN: usize = max_encoded_len!(
+        DriveState,
+        BusDriveState<13>,
+        BusDriveState<8>,
+        DriveState,
+        CpuRegs,
+    );
+}
+
+impl Decode for Cpu {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        Ok(Self {
+            phi2_out: Decode::decode(r)?,
+            a_out: Decode::decode(r)?,
+            db_out: Decode::decode(r)?,
+            rw_out: Decode::decode(r)?,
+            reg: Decode::decode(r)?,
+        })
+    }
+}
+
+impl Combine for Cpu {
+    fn combine_with(&self, other: &Self) -> Self {
+        Self {
+            phi2_out: self.phi2_out.combine_with(&other.phi2_out),
+            a_out: self.a_out.combine_with(&other.a_out),
+            db_out: self.db_out.combine_with(&other.db_out),
+            rw_out: self.rw_out.combine_with(&other.rw_out),
+            reg: self.reg.combine_with(&other.reg),
+        }
+    }
+}
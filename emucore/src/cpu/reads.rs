@@ -24,8 +24,4 @@ pub const fn new(lines: CpuLineReads, regs: CpuRegReads) -> Self {
             reg: regs,
         }
     }
-
-    pub const fn update(&mut self, regs: CpuRegReads) {
-        self.reg = regs;
-    }
 }
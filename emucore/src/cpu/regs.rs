@@ -1,4 +1,80 @@
-use crate::common::{BitReg, MBitReg};
+use crate::common::{
+    codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer, max_encoded_len},
+    combine::Combine,
+    reg::{BitReg, MBitReg},
+};
+use core::slice;
+use strum_macros::Display;
+
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum CpuReg {
+    #[strum(to_string = "instruction cycle")]
+    InstrCycle,
+
+    #[strum(to_string = "A")]
+    A,
+
+    #[strum(to_string = "X")]
+    X,
+
+    #[strum(to_string = "Y")]
+    Y,
+
+    #[strum(to_string = "PC")]
+    Pc,
+
+    #[strum(to_string = "S")]
+    S,
+
+    #[strum(to_string = "N flag")]
+    N,
+
+    #[strum(to_string = "V flag")]
+    V,
+
+    #[strum(to_string = "B flag")]
+    B,
+
+    #[strum(to_string = "D flag")]
+    D,
+
+    #[strum(to_string = "I flag")]
+    I,
+
+    #[strum(to_string = "Z flag")]
+    Z,
+
+    #[strum(to_string = "C flag")]
+    C,
+}
+
+impl CpuReg {
+    pub const ALL: [Self; 13] = [
+        Self::InstrCycle,
+        Self::A,
+        Self::X,
+        Self::Y,
+        Self::Pc,
+        Self::S,
+        Self::N,
+        Self::V,
+        Self::B,
+        Self::D,
+        Self::I,
+        Self::Z,
+        Self::C,
+    ];
+
+    #[must_use]
+    pub const fn width(self) -> usize {
+        match self {
+            Self::InstrCycle => 3,
+            Self::A | Self::X | Self::Y | Self::S => 8,
+            Self::Pc => 16,
+            Self::N | Self::V | Self::B | Self::D | Self::I | Self::Z | Self::C => 1,
+        }
+    }
+}
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct CpuRegs {
@@ -35,4 +111,110 @@ pub fn new() -> Self {
             c: BitReg::Unknown,
         }
     }
+
+    #[must_use]
+    pub fn bits(&self, reg: CpuReg) -> &[BitReg] {
+        match reg {
+            CpuReg::InstrCycle => &self.instr_cycle[..],
+            CpuReg::A => &self.a[..],
+            CpuReg::X => &self.x[..],
+            CpuReg::Y => &self.y[..],
+            CpuReg::Pc => &self.pc[..],
+            CpuReg::S => &self.s[..],
+            CpuReg::N => slice::from_ref(&self.n),
+            CpuReg::V => slice::from_ref(&self.v),
+            CpuReg::B => slice::from_ref(&self.b),
+            CpuReg::D => slice::from_ref(&self.d),
+            CpuReg::I => slice::from_ref(&self.i),
+            CpuReg::Z => slice::from_ref(&self.z),
+            CpuReg::C => slice::from_ref(&self.c),
+        }
+    }
+
+    pub fn bits_mut(&mut self, reg: CpuReg) -> &mut [BitReg] {
+        match reg {
+            CpuReg::InstrCycle => &mut self.instr_cycle[..],
+            CpuReg::A => &mut self.a[..],
+            CpuReg::X => &mut self.x[..],
+            CpuReg::Y => &mut self.y[..],
+            CpuReg::Pc => &mut self.pc[..],
+            CpuReg::S => &mut self.s[..],
+            CpuReg::N => slice::from_mut(&mut self.n),
+            CpuReg::V => slice::from_mut(&mut self.v),
+            CpuReg::B => slice::from_mut(&mut self.b),
+            CpuReg::D => slice::from_mut(&mut self.d),
+            CpuReg::I => slice::from_mut(&mut self.i),
+            CpuReg::Z => slice::from_mut(&mut self.z),
+            CpuReg::C => slice::from_mut(&mut self.c),
+        }
+    }
+}
+
+impl Encode for CpuRegs {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        self.instr_cycle.encode(w)?;
+        self.a.encode(w)?;
+        self.x.encode(w)?;
+        self.y.encode(w)?;
+        self.pc.encode(w)?;
+        self.s.encode(w)?;
+        self.n.encode(w)?;
+        self.v.encode(w)?;
+        self.b.encode(w)?;
+        self.d.encode(w)?;
+        self.i.encode(w)?;
+        self.z.encode(w)?;
+        self.c.encode(w)
+    }
+}
+
+impl MaxEncodedLen for CpuRegs {
+    const MAX_ENCODED_LEN: usize = max_encoded_len!(
+        MBitReg<3>,
+        MBitReg<8>,
+        MBitReg<8>,
+        MBitReg<8>,
+        MBitReg<16>,
+        MBitReg<8>,
+    ) + 7 * BitReg::MAX_ENCODED_LEN;
+}
+
+impl Decode for CpuRegs {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        Ok(Self {
+            instr_cycle: Decode::decode(r)?,
+            a: Decode::decode(r)?,
+            x: Decode::decode(r)?,
+            y: Decode::decode(r)?,
+            pc: Decode::decode(r)?,
+            s: Decode::decode(r)?,
+            n: Decode::decode(r)?,
+            v: Decode::decode(r)?,
+            b: Decode::decode(r)?,
+            d: Decode::decode(r)?,
+            i: Decode::decode(r)?,
+            z: Decode::decode(r)?,
+            c: Decode::decode(r)?,
+        })
+    }
+}
+
+impl Combine for CpuRegs {
+    fn combine_with(&self, other: &Self) -> Self {
+        Self {
+            instr_cycle: self.instr_cycle.combine_with(&other.instr_cycle),
+            a: self.a.combine_with(&other.a),
+            x: self.x.combine_with(&other.x),
+            y: self.y.combine_with(&other.y),
+            pc: self.pc.combine_with(&other.pc),
+            s: self.s.combine_with(&other.s),
+            n: self.n.combine_with(&other.n),
+            v: self.v.combine_with(&other.v),
+            b: self.b.combine_with(&other.b),
+            d: self.d.combine_with(&other.d),
+            i: self.i.combine_with(&other.i),
+            z: self.z.combine_with(&other.z),
+            c: self.c.combine_with(&other.c),
+        }
+    }
 }
@@ -0,0 +1,299 @@
+use crate::{
+    common::{
+        codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer},
+        cond::base::BaseCondition,
+        line::single::DriveState,
+    },
+    full::line_reads::{EmuLineStates, TIA_ADDR_LINES},
+};
+
+pub const COLOR_CLOCKS_PER_CYCLE: u16 = 3;
+pub const COLOR_CLOCKS_PER_SCANLINE: u16 = 228;
+pub const SCANLINES_PER_FRAME: u16 = 262;
+
+/// The longest frame once the program has started using VSYNC, after which the beam starts a new
+/// frame anyway, as a TV would lose vertical hold.
+pub const MAX_SCANLINES_PER_FRAME: u16 = SCANLINES_PER_FRAME * 2;
+
+const VSYNC: u16 = 0x00;
+const WSYNC: u16 = 0x02;
+
+/// The position of the TIA's electron beam.
+///
+/// Until the program first turns on VSYNC, the beam free-runs at NTSC timing, so frames are
+/// [`SCANLINES_PER_FRAME`] scanlines long. From then on a frame starts at the first scanline after
+/// VSYNC is turned off. A write to WSYNC holds RDY low until the next scanline starts.
+///
+/// Only TIA writes with a known register and known data bits are followed.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Beam {
+    frame: u64,
+    scanline: u16,
+    color_clock: u16,
+    /// Whether VSYNC is on.
+    vsync: bool,
+    /// Whether the program has turned on VSYNC since power-on.
+    synced: bool,
+    /// Whether VSYNC has been turned off since the frame started, so the next scanline starts a
+    /// new frame.
+    frame_ending: bool,
+    /// Whether a WSYNC write is holding the CPU until the next scanline.
+    wsync: bool,
+    /// The TIA's drive on RDY, which is pulled up unless WSYNC holds it low.
+    pub rdy_out: DriveState,
+}
+
+impl Default for Beam {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Beam {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            frame: 0,
+            scanline: 0,
+            color_clock: 0,
+            vsync: false,
+            synced: false,
+            frame_ending: false,
+            wsync: false,
+            rdy_out: Self::rdy_drive(false),
+        }
+    }
+
+    const fn rdy_drive(held: bool) -> DriveState {
+        DriveState {
+            low: held,
+            weak_high: !held,
+            ..DriveState::none_enabled()
+        }
+    }
+
+    #[must_use]
+    pub const fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    #[must_use]
+    pub const fn scanline(&self) -> u16 {
+        self.scanline
+    }
+
+    #[must_use]
+    pub const fn color_clock(&self) -> u16 {
+        self.color_clock
+    }
+
+    #[cfg(any(test, feature = "untested-analyses"))]
+    #[must_use]
+    pub const fn is_frame_start(&self) -> bool {
+        self.scanline == 0 && self.color_clock == 0
+    }
+
+    const fn frame_len(&self) -> u16 {
+        if self.synced {
+            MAX_SCANLINES_PER_FRAME
+        } else {
+            SCANLINES_PER_FRAME
+        }
+    }
+
+    pub const fn advance_cycle(&mut self) {
+        self.color_clock += COLOR_CLOCKS_PER_CYCLE;
+
+        if self.color_clock >= COLOR_CLOCKS_PER_SCANLINE {
+            self.color_clock -= COLOR_CLOCKS_PER_SCANLINE;
+            self.scanline += 1;
+            self.wsync = false;
+            self.rdy_out = Self::rdy_drive(false);
+
+            if self.frame_ending || self.scanline >= self.frame_len() {
+                self.scanline = 0;
+                self.frame += 1;
+                self.frame_ending = false;
+            }
+        }
+    }
+
+    /// Follows a write to VSYNC or WSYNC on the bus.
+    pub fn observe_bus(&mut self, lines: &EmuLineStates) {
+        if lines.tia_write() != BaseCondition::Yes {
+            return;
+        }
+
+        let Some(reg) = lines.a[..TIA_ADDR_LINES]
+            .iter()
+            .enumerate()
+            .try_fold(0, |reg, (i, bit)| {
+                Some(reg | u16::from(bit.as_bool()?) << i)
+            })
+        else {
+            return;
+        };
+
+        match (reg, lines.db[1].as_bool()) {
+            (VSYNC, Some(true)) => {
+                self.vsync = true;
+                self.synced = true;
+            }
+            (VSYNC, Some(false)) => {
+                self.frame_ending |= self.vsync;
+                self.vsync = false;
+            }
+            (WSYNC, _) => {
+                self.wsync = true;
+                self.rdy_out = Self::rdy_drive(true);
+            }
+            _ => (),
+        }
+    }
+
+    /// Writes the VSYNC and WSYNC state, which is kept apart from the counters.
+    pub(crate) fn encode_sync(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        w.write_u8(
+            u8::from(self.vsync)
+                | u8::from(self.synced) << 1
+                | u8::from(self.frame_ending) << 2
+                | u8::from(self.wsync) << 3,
+        )
+    }
+
+    pub(crate) fn decode_sync(&mut self, r: &mut Reader<'_>) -> Result<(), CodecError> {
+        let bits = r.read_u8()?;
+        if bits >> 4 != 0 {
+            return Err(CodecError::InvalidValue);
+        }
+
+        self.vsync = bits & 1 != 0;
+        self.synced = bits >> 1 & 1 != 0;
+        self.frame_ending = bits >> 2 & 1 != 0;
+        self.wsync = bits >> 3 & 1 != 0;
+        self.rdy_out = Self::rdy_drive(self.wsync);
+        Ok(())
+    }
+}
+
+impl Encode for Beam {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        w.write_u64(self.frame)?;
+        w.write_u16(self.scanline)?;
+        w.write_u16(self.color_clock)
+    }
+}
+
+impl MaxEncodedLen for Beam {
+    const MAX_ENCODED_LEN: usize = 8 + 2 + 2;
+}
+
+impl Decode for Beam {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let frame = r.read_u64()?;
+        let scanline = r.read_u16()?;
+        let color_clock = r.read_u16()?;
+
+        if scanline >= SCANLINES_PER_FRAME || color_clock >= COLOR_CLOCKS_PER_SCANLINE {
+            return Err(CodecError::InvalidValue);
+        }
+
+        Ok(Self {
+            frame,
+            scanline,
+            color_clock,
+            ..Self::new()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::read::{multi::MultiRead, single::SingleRead};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, 0, 0, 0)]
+    #[case(1, 0, 0, 3)]
+    #[case(76, 0, 1, 0)]
+    #[case(77, 0, 1, 3)]
+    #[case(76 * 262, 1, 0, 0)]
+    #[case(76 * 262 * 2 + 76 * 5 + 10, 2, 5, 30)]
+    fn advance(
+        #[case] cycles: u32,
+        #[case] frame: u64,
+        #[case] scanline: u16,
+        #[case] color_clock: u16,
+    ) {
+        let mut beam = Beam::new();
+        for _ in 0..cycles {
+            beam.advance_cycle();
+        }
+
+        assert_eq!(beam.frame(), frame);
+        assert_eq!(beam.scanline(), scanline);
+        assert_eq!(beam.color_clock(), color_clock);
+    }
+
+    fn write(beam: &mut Beam, reg: u16, data: u16) {
+        let mut lines = EmuLineStates::new();
+        lines.a = MultiRead::from_value(0x40 | reg);
+        lines.rw = SingleRead::Low;
+        lines.db = MultiRead::from_value(data);
+        beam.observe_bus(&lines);
+    }
+
+    fn advance_scanlines(beam: &mut Beam, scanlines: u16) {
+        for _ in 0..scanlines * COLOR_CLOCKS_PER_SCANLINE / COLOR_CLOCKS_PER_CYCLE {
+            beam.advance_cycle();
+        }
+    }
+
+    #[test]
+    fn starts_frames_after_vsync() {
+        let mut beam = Beam::new();
+        advance_scanlines(&mut beam, 10);
+
+        write(&mut beam, VSYNC, 0x02);
+        advance_scanlines(&mut beam, 3);
+        write(&mut beam, VSYNC, 0x00);
+        assert_eq!((beam.frame(), beam.scanline()), (0, 13));
+
+        advance_scanlines(&mut beam, 1);
+        assert_eq!((beam.frame(), beam.scanline()), (1, 0));
+
+        // Synced frames no longer end after SCANLINES_PER_FRAME scanlines.
+        advance_scanlines(&mut beam, SCANLINES_PER_FRAME + 10);
+        assert_eq!(
+            (beam.frame(), beam.scanline()),
+            (1, SCANLINES_PER_FRAME + 10)
+        );
+    }
+
+    #[test]
+    fn wsync_holds_rdy_until_next_scanline() {
+        let mut beam = Beam::new();
+        beam.advance_cycle();
+
+        write(&mut beam, WSYNC, 0);
+        assert_eq!(beam.rdy_out.read(), Some(SingleRead::Low));
+
+        advance_scanlines(&mut beam, 1);
+        assert_eq!(beam.rdy_out.read(), Some(SingleRead::High));
+    }
+
+    #[test]
+    fn ignores_writes_elsewhere() {
+        let mut beam = Beam::new();
+        write(&mut beam, 0x09, 0xff);
+
+        let mut lines = EmuLineStates::new();
+        lines.a = MultiRead::from_value(WSYNC);
+        lines.rw = SingleRead::High;
+        beam.observe_bus(&lines);
+
+        assert_eq!(beam, Beam::new());
+    }
+}
@@ -0,0 +1,234 @@
+use crate::{
+    common::{
+        line::ident::LineIdent,
+        read::{multi::MultiRead, single::SingleRead},
+    },
+    cpu::regs::CpuReg,
+    full::{
+        Emulator,
+        line_reads::{BUSES, LINE_NAMES},
+    },
+    riot::{RAM_SIZE, regs::RiotReg},
+};
+use core::fmt;
+
+/// The address of the first RIOT RAM byte in the CPU's address space.
+const RAM_BASE: usize = 0x80;
+
+const CPU_BITS: usize = {
+    let mut bits = 0;
+    let mut i = 0;
+    while i < CpuReg::ALL.len() {
+        bits += CpuReg::ALL[i].width();
+        i += 1;
+    }
+    bits
+};
+
+const RIOT_BITS: usize = {
+    let mut bits = 0;
+    let mut i = 0;
+    while i < RiotReg::ALL.len() {
+        bits += RiotReg::ALL[i].width();
+        i += 1;
+    }
+    bits
+};
+
+const BUS_BITS: usize = {
+    let mut bits = 0;
+    let mut i = 0;
+    while i < BUSES.len() {
+        bits += BUSES[i].1;
+        i += 1;
+    }
+    bits
+};
+
+/// The number of bits of emulator state, as iterated by [`StateBit::all`].
+pub const STATE_BIT_COUNT: usize =
+    CPU_BITS + RIOT_BITS + RAM_SIZE * 8 + BUS_BITS + LINE_NAMES.len();
+
+/// A single bit of emulator state.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum StateBit {
+    Cpu { reg: CpuReg, bit: usize },
+    Riot { reg: RiotReg, bit: usize },
+    Ram { addr: usize, bit: usize },
+    Line { ident: LineIdent },
+}
+
+impl fmt::Display for StateBit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cpu { reg, bit } => write!(f, "CPU {reg} bit {bit}"),
+            Self::Riot { reg, bit } => write!(f, "RIOT {reg} bit {bit}"),
+            Self::Ram { addr, bit } => write!(f, "RIOT RAM ${:02X} bit {bit}", RAM_BASE + addr),
+            Self::Line { ident } => write!(f, "line {ident}"),
+        }
+    }
+}
+
+impl StateBit {
+    pub fn cpu_reg(reg: CpuReg) -> impl Iterator<Item = Self> {
+        (0..reg.width()).map(move |bit| Self::Cpu { reg, bit })
+    }
+
+    pub fn riot_reg(reg: RiotReg) -> impl Iterator<Item = Self> {
+        (0..reg.width()).map(move |bit| Self::Riot { reg, bit })
+    }
+
+    pub fn ram_byte(addr: usize) -> impl Iterator<Item = Self> {
+        (0..8).map(move |bit| Self::Ram { addr, bit })
+    }
+
+    pub fn bus(bus_name: &'static str, width: usize) -> impl Iterator<Item = Self> {
+        (0..width).map(move |bit| Self::Line {
+            ident: LineIdent::BusLine { bus_name, bit },
+        })
+    }
+
+    /// Iterates over every bit of emulator state, in the order registers, RAM, then lines.
+    pub fn all() -> impl Iterator<Item = Self> {
+        let cpu = CpuReg::ALL.into_iter().flat_map(Self::cpu_reg);
+        let riot = RiotReg::ALL.into_iter().flat_map(Self::riot_reg);
+        let ram = (0..RAM_SIZE).flat_map(Self::ram_byte);
+        let buses = BUSES
+            .into_iter()
+            .flat_map(|(name, width)| Self::bus(name, width));
+        let lines = LINE_NAMES.into_iter().map(|name| Self::Line {
+            ident: LineIdent::UniqueLine { name },
+        });
+
+        cpu.chain(riot).chain(ram).chain(buses).chain(lines)
+    }
+
+    /// The position of the bit in [`StateBit::all`], or `None` if there is no such bit.
+    #[must_use]
+    pub fn index(self) -> Option<usize> {
+        match self {
+            Self::Cpu { reg, bit } => (bit < reg.width()).then(|| {
+                CpuReg::ALL
+                    .into_iter()
+                    .take_while(|&other| other != reg)
+                    .map(CpuReg::width)
+                    .sum::<usize>()
+                    + bit
+            }),
+            Self::Riot { reg, bit } => (bit < reg.width()).then(|| {
+                RiotReg::ALL
+                    .into_iter()
+                    .take_while(|&other| other != reg)
+                    .map(RiotReg::width)
+                    .sum::<usize>()
+                    + CPU_BITS
+                    + bit
+            }),
+            Self::Ram { addr, bit } => {
+                (addr < RAM_SIZE && bit < 8).then_some(CPU_BITS + RIOT_BITS + addr * 8 + bit)
+            }
+            Self::Line {
+                ident: LineIdent::BusLine { bus_name, bit },
+            } => {
+                let i = BUSES.iter().position(|&(name, _)| name == bus_name)?;
+                (bit < BUSES[i].1).then(|| {
+                    BUSES[..i].iter().map(|&(_, width)| width).sum::<usize>()
+                        + CPU_BITS
+                        + RIOT_BITS
+                        + RAM_SIZE * 8
+                        + bit
+                })
+            }
+            Self::Line {
+                ident: LineIdent::UniqueLine { name },
+            } => LINE_NAMES
+                .iter()
+                .position(|&other| other == name)
+                .map(|i| CPU_BITS + RIOT_BITS + RAM_SIZE * 8 + BUS_BITS + i),
+        }
+    }
+}
+
+impl Emulator {
+    #[must_use]
+    pub fn bit(&self, bit: StateBit) -> Option<SingleRead> {
+        match bit {
+            StateBit::Cpu { reg, bit } => self.cpu.regs().bits(reg).get(bit).copied(),
+            StateBit::Riot { reg, bit } => self.riot.regs().bits(reg).get(bit).copied(),
+            StateBit::Ram { addr, bit } => self.riot.ram().get(addr)?.get(bit).copied(),
+            StateBit::Line { ident } => match ident {
+                LineIdent::UniqueLine { name } => self.line_states.net(name)?.first().copied(),
+                LineIdent::BusLine { bus_name, bit } => {
+                    self.line_states.net(bus_name)?.get(bit).copied()
+                }
+            },
+        }
+    }
+
+    pub fn bit_mut(&mut self, bit: StateBit) -> Option<&mut SingleRead> {
+        match bit {
+            StateBit::Cpu { reg, bit } => self.cpu.regs_mut().bits_mut(reg).get_mut(bit),
+            StateBit::Riot { reg, bit } => self.riot.regs_mut().bits_mut(reg).get_mut(bit),
+            StateBit::Ram { addr, bit } => self.riot.ram_mut().get_mut(addr)?.get_mut(bit),
+            StateBit::Line { ident } => match ident {
+                LineIdent::UniqueLine { name } => self.line_states.net_mut(name)?.first_mut(),
+                LineIdent::BusLine { bus_name, bit } => {
+                    self.line_states.net_mut(bus_name)?.get_mut(bit)
+                }
+            },
+        }
+    }
+
+    /// Iterates over every bit of emulator state along with its current value.
+    pub fn bits(&self) -> impl Iterator<Item = (StateBit, SingleRead)> + '_ {
+        StateBit::all().filter_map(|bit| Some((bit, self.bit(bit)?)))
+    }
+
+    /// Iterates over the values the given bits could hold, with the first bit as the least
+    /// significant.
+    ///
+    /// Returns `None` if any bit does not exist or more than 16 bits are given.
+    #[must_use]
+    pub fn possible_values(&self, bits: &[StateBit]) -> Option<impl Iterator<Item = u16>> {
+        let mut reads: MultiRead<16> = [SingleRead::Low; _].into();
+
+        for (read, &bit) in reads.iter_mut().zip(bits) {
+            *read = self.bit(bit)?;
+        }
+
+        (bits.len() <= reads.len()).then(|| reads.iter_possible_reads())
+    }
+
+    /// The program counter, if every bit of it is known.
+    #[must_use]
+    pub fn known_pc(&self) -> Option<u16> {
+        self.cpu
+            .regs()
+            .bits(CpuReg::Pc)
+            .iter()
+            .enumerate()
+            .try_fold(0, |pc, (i, bit)| Some(pc | u16::from(bit.as_bool()?) << i))
+    }
+
+    #[must_use]
+    pub fn unknown_count(&self) -> usize {
+        self.bits()
+            .filter(|(_, read)| *read == SingleRead::Unknown)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexes_bits_in_iteration_order() {
+        assert_eq!(StateBit::all().count(), STATE_BIT_COUNT);
+
+        for (i, bit) in StateBit::all().enumerate() {
+            assert_eq!(bit.index(), Some(i), "{bit}");
+        }
+        assert_eq!(StateBit::Ram { addr: 0, bit: 8 }.index(), None);
+    }
+}
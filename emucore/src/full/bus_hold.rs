@@ -0,0 +1,244 @@
+use crate::{
+    common::{
+        codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer, varint_max_len},
+        combine::Combine,
+        line::{error::LineError, ident::LineIdent, single::DriveState},
+        read::single::SingleRead,
+    },
+    full::line_reads::{LINE_COUNT, NET_COUNT, line_ident, line_index, net_index},
+};
+use core::array;
+
+/// How a net behaves while nothing drives it.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BusHold {
+    /// The number of half-cycles after which a held value decays to Unknown, or `None` to hold it
+    /// for as long as the net floats.
+    pub decay: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct HeldBit {
+    value: SingleRead,
+    age: u32,
+}
+
+/// The bus-hold setting of every net, along with the value each line last held.
+///
+/// Nets without bus-hold read Unknown while they float, as [`DriveState::read`] gives.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BusHoldState {
+    nets: [Option<BusHold>; NET_COUNT],
+    held: [HeldBit; LINE_COUNT],
+}
+
+impl Default for BusHoldState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BusHoldState {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            nets: [None; _],
+            held: [HeldBit {
+                value: SingleRead::Unknown,
+                age: 0,
+            }; _],
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, net: &str) -> Option<BusHold> {
+        self.nets[net_index(net)?]
+    }
+
+    /// Sets the bus-hold of a net, returning `false` if there is no such net.
+    pub fn set(&mut self, net: &str, hold: Option<BusHold>) -> bool {
+        net_index(net).map(|i| self.nets[i] = hold).is_some()
+    }
+
+    /// Ages every held value by one half-cycle.
+    pub fn advance_half_cycle(&mut self) {
+        for held in &mut self.held {
+            held.age = held.age.saturating_add(1);
+        }
+    }
+
+    /// Reads a line, falling back on its held value for as long as it could be floating.
+    pub fn resolve(
+        &mut self,
+        ident: LineIdent,
+        drive: DriveState,
+    ) -> Result<SingleRead, LineError> {
+        let read = drive.read_ok(ident)?;
+
+        let (Some(config), Some(i)) = (self.get(ident.net_name()), line_index(ident)) else {
+            return Ok(read);
+        };
+
+        let held = &mut self.held[i];
+        if !drive.high_z {
+            *held = HeldBit {
+                value: read,
+                age: 0,
+            };
+            return Ok(read);
+        }
+
+        let held_value = if config.decay.is_some_and(|decay| held.age >= decay) {
+            SingleRead::Unknown
+        } else {
+            held.value
+        };
+
+        let driven = DriveState {
+            high_z: false,
+            ..drive
+        }
+        .read();
+        held.value = driven.map_or(held_value, |driven| driven.combine_with(&held_value));
+        Ok(held.value)
+    }
+
+    fn decay(&self, line: usize) -> Option<u32> {
+        self.get(line_ident(line)?.net_name())?.decay
+    }
+}
+
+impl Combine for BusHoldState {
+    fn combine_with(&self, other: &Self) -> Self {
+        Self {
+            nets: self.nets,
+            held: array::from_fn(|i| HeldBit {
+                value: self.held[i].value.combine_with(&other.held[i].value),
+                age: self.held[i].age.max(other.held[i].age),
+            }),
+        }
+    }
+}
+
+impl Encode for BusHoldState {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        for hold in &self.nets {
+            match hold {
+                None => w.write_u8(0)?,
+                Some(BusHold { decay: None }) => w.write_u8(1)?,
+                Some(BusHold { decay: Some(decay) }) => {
+                    w.write_u8(2)?;
+                    w.write_varint(u64::from(*decay))?;
+                }
+            }
+        }
+
+        for (i, held) in self.held.iter().enumerate() {
+            held.value.encode(w)?;
+
+            // Ages only matter up to the decay, so they are clamped to keep them short.
+            if let Some(decay) = self.decay(i) {
+                w.write_varint(u64::from(held.age.min(decay)))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Every net may have a decay, and every line an age, each as a full-width varint.
+impl MaxEncodedLen for BusHoldState {
+    const MAX_ENCODED_LEN: usize = NET_COUNT * (1 + varint_max_len(u32::BITS))
+        + LINE_COUNT * (SingleRead::MAX_ENCODED_LEN + varint_max_len(u32::BITS));
+}
+
+impl Decode for BusHoldState {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let mut res = Self::new();
+
+        for hold in &mut res.nets {
+            *hold = match r.read_u8()? {
+                0 => None,
+                1 => Some(BusHold { decay: None }),
+                2 => Some(BusHold {
+                    decay: Some(
+                        u32::try_from(r.read_varint()?).map_err(|_| CodecError::InvalidValue)?,
+                    ),
+                }),
+                _ => return Err(CodecError::InvalidValue),
+            };
+        }
+
+        for i in 0..LINE_COUNT {
+            let value = SingleRead::decode(r)?;
+            let age = match res.decay(i) {
+                Some(_) => u32::try_from(r.read_varint()?).map_err(|_| CodecError::InvalidValue)?,
+                None => 0,
+            };
+            res.held[i] = HeldBit { value, age };
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::signal::LineSignal,
+        full::{Emulator, line_reads::NETS, save_state::SAVE_STATE_MAX_LEN},
+    };
+
+    #[test]
+    fn saves_worst_case_state() {
+        let mut emu = Emulator::new();
+        let weak = LineSignal::WeakLow.into();
+        emu.cpu.drives_mut().for_each(|drive| *drive = weak);
+        emu.riot.drives_mut().for_each(|drive| *drive = weak);
+
+        let hold = &mut emu.line_states.hold;
+        for net in &NETS {
+            hold.set(
+                net.name,
+                Some(BusHold {
+                    decay: Some(u32::MAX),
+                }),
+            );
+        }
+        for held in &mut hold.held {
+            held.age = u32::MAX;
+        }
+
+        let mut buf = [0; SAVE_STATE_MAX_LEN];
+        let len = emu.save_state(&mut buf).unwrap();
+        assert_eq!(
+            Emulator::load_state(&buf[..len]).unwrap().line_states.hold,
+            emu.line_states.hold
+        );
+    }
+
+    #[test]
+    fn holds_then_decays() {
+        let mut hold = BusHoldState::new();
+        assert!(hold.set("db", Some(BusHold { decay: Some(2) })));
+        assert!(!hold.set("nope", None));
+
+        let bit = |bit| LineIdent::BusLine {
+            bus_name: "db",
+            bit,
+        };
+        let floating = LineSignal::HighZ.into();
+
+        assert_eq!(
+            hold.resolve(bit(0), SingleRead::High.into()),
+            Ok(SingleRead::High)
+        );
+        hold.advance_half_cycle();
+        assert_eq!(hold.resolve(bit(0), floating), Ok(SingleRead::High));
+        assert_eq!(hold.resolve(bit(1), floating), Ok(SingleRead::Unknown));
+
+        hold.advance_half_cycle();
+        assert_eq!(hold.resolve(bit(0), floating), Ok(SingleRead::Unknown));
+    }
+}
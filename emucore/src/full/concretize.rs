@@ -0,0 +1,161 @@
+use crate::{
+    common::{
+        line::single::DriveState,
+        rng::{BitSource, SplitMix64},
+    },
+    full::{Emulator, bits::StateBit, power_on::PowerOnPolicy},
+};
+
+/// Which parts of the emulator's state an operation applies to.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct StateScope {
+    pub cpu: bool,
+    pub riot: bool,
+    pub ram: bool,
+    pub lines: bool,
+}
+
+impl StateScope {
+    pub const ALL: Self = Self {
+        cpu: true,
+        riot: true,
+        ram: true,
+        lines: true,
+    };
+
+    pub const RAM: Self = Self {
+        cpu: false,
+        riot: false,
+        ram: true,
+        lines: false,
+    };
+
+    #[must_use]
+    pub const fn contains(self, bit: StateBit) -> bool {
+        match bit {
+            StateBit::Cpu { .. } => self.cpu,
+            StateBit::Riot { .. } => self.riot,
+            StateBit::Ram { .. } => self.ram,
+            StateBit::Line { .. } => self.lines,
+        }
+    }
+}
+
+impl Emulator {
+    /// Replaces every Unknown bit and undecided output drive with a random value drawn from
+    /// `seed`, giving a fully known state which the original state covers.
+    pub fn concretize(&mut self, seed: u64) {
+        self.concretize_scope(seed, StateScope::ALL);
+    }
+
+    /// Replaces every Unknown bit within `scope` with a random value drawn from `seed`, along with
+    /// the output drives of the CPU and RIOT when they are in scope.
+    ///
+    /// Bits are drawn in the order of [`StateBit::all`], then drives in component order, so the
+    /// same seed and state always give the same result.
+    pub fn concretize_scope(&mut self, seed: u64, scope: StateScope) {
+        PowerOnPolicy::Seeded { seed }.fill_unknown(self, scope);
+
+        let mut rng = BitSource::new(SplitMix64::new(!seed).next_u64());
+        if scope.cpu {
+            self.cpu
+                .drives_mut()
+                .for_each(|drive| pick_drive(drive, &mut rng));
+        }
+        if scope.riot {
+            self.riot
+                .drives_mut()
+                .for_each(|drive| pick_drive(drive, &mut rng));
+        }
+    }
+}
+
+/// Narrows a drive with several possible signals down to one of them.
+fn pick_drive(drive: &mut DriveState, rng: &mut BitSource) {
+    let count = drive.signals().count();
+    if count <= 1 {
+        return;
+    }
+
+    let choice = loop {
+        let choice = (0..3).fold(0, |acc, _| acc << 1 | usize::from(rng.next_bit()));
+        if choice < count {
+            break choice;
+        }
+    };
+
+    if let Some(signal) = drive.signals().nth(choice) {
+        *drive = signal.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{
+            combine::Combine, line::error::ClockEdge, read::single::SingleRead, signal::LineSignal,
+        },
+        full::{ext_drives::ExtDrives, line_reads::NETS},
+    };
+
+    #[test]
+    fn concretizes_deterministically() {
+        let emu = Emulator::new();
+
+        let mut a = emu.clone();
+        a.concretize(7);
+        let mut b = emu.clone();
+        b.concretize(7);
+
+        assert_eq!(a.unknown_count(), 0);
+        assert_eq!(a, b);
+        assert!(emu.covers(&a));
+    }
+
+    #[test]
+    fn leaves_no_unknown_lines() {
+        let either = DriveState::from(false).combine_with(&true.into());
+
+        let mut emu = Emulator::new();
+        emu.cpu.a_out = [either; _].into();
+        emu.cpu.db_out = [either; _].into();
+        emu.cpu.rw_out = either;
+        emu.riot.pa_out = [LineSignal::WeakHigh.into(); _].into();
+        emu.riot.pa_out[2] = either;
+        emu.concretize(3);
+
+        let mut ext = ExtDrives::new();
+        ext.inp1 = [LineSignal::WeakHigh.into(); _].into();
+        ext.inp2 = [LineSignal::WeakHigh.into(); _].into();
+        emu.update(&ext, ClockEdge::Rising).unwrap();
+
+        for net in NETS
+            .iter()
+            .filter(|net| net.pins.iter().any(|pin| pin.direction.can_drive()))
+        {
+            let lines = emu.line_states.net(net.name).unwrap();
+            assert!(
+                !lines.contains(&SingleRead::Unknown),
+                "{} is Unknown",
+                net.name
+            );
+        }
+    }
+
+    #[test]
+    fn concretizes_only_scope() {
+        let fresh = Emulator::new();
+        let mut emu = fresh.clone();
+        emu.concretize_scope(1, StateScope::RAM);
+
+        for (bit, read) in emu.bits() {
+            if matches!(bit, StateBit::Ram { .. }) {
+                assert_ne!(read, SingleRead::Unknown);
+            } else {
+                assert_eq!(Some(read), fresh.bit(bit));
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::full::{
+    Emulator, contention::ContentionPolicy, power_on::PowerOnConfig, settle::DEFAULT_SETTLE_LIMIT,
+};
+use core::num::NonZeroU32;
+
+/// Settings which shape how an emulator is set up and run.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct EmulatorConfig {
+    pub power_on: PowerOnConfig,
+    pub contention: ContentionPolicy,
+    pub settle_limit: NonZeroU32,
+}
+
+impl EmulatorConfig {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            power_on: PowerOnConfig::new(),
+            contention: ContentionPolicy::Error,
+            settle_limit: DEFAULT_SETTLE_LIMIT,
+        }
+    }
+}
+
+impl Default for EmulatorConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Emulator {
+    /// Creates an emulator at power-on, with its undefined state filled in as `config` says.
+    #[must_use]
+    pub fn with_config(config: &EmulatorConfig) -> Self {
+        let mut emu = Self::new();
+        config.power_on.apply(&mut emu);
+        emu.contention.policy = config.contention;
+        emu.settle_limit = config.settle_limit;
+        emu
+    }
+}
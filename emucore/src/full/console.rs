@@ -0,0 +1,156 @@
+use crate::{common::line::single::DriveState, full::ext_drives::ExtDrives};
+use strum_macros::Display;
+
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum TvType {
+    #[strum(to_string = "black & white")]
+    BlackWhite,
+
+    #[strum(to_string = "colour")]
+    Colour,
+}
+
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum Difficulty {
+    #[strum(to_string = "A")]
+    A,
+
+    #[strum(to_string = "B")]
+    B,
+}
+
+/// The switches and buttons on the front panel of the console.
+///
+/// The Game Select and Game Reset buttons are momentary, so a press is held for a chosen number of
+/// frames and released by [`ConsoleSwitches::end_frame`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ConsoleSwitches {
+    pub tv_type: TvType,
+    pub left_difficulty: Difficulty,
+    pub right_difficulty: Difficulty,
+    select_frames: u32,
+    reset_frames: u32,
+}
+
+impl Default for ConsoleSwitches {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConsoleSwitches {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            tv_type: TvType::Colour,
+            left_difficulty: Difficulty::B,
+            right_difficulty: Difficulty::B,
+            select_frames: 0,
+            reset_frames: 0,
+        }
+    }
+
+    pub const fn press_select(&mut self, frames: u32) {
+        self.select_frames = frames;
+    }
+
+    pub const fn press_reset(&mut self, frames: u32) {
+        self.reset_frames = frames;
+    }
+
+    #[must_use]
+    pub const fn select_pressed(&self) -> bool {
+        self.select_frames > 0
+    }
+
+    #[must_use]
+    pub const fn reset_pressed(&self) -> bool {
+        self.reset_frames > 0
+    }
+
+    pub const fn end_frame(&mut self) {
+        self.select_frames = self.select_frames.saturating_sub(1);
+        self.reset_frames = self.reset_frames.saturating_sub(1);
+    }
+
+    /// Drives the switch lines. Each switch grounds its line in the low position and leaves it to
+    /// the RIOT's pull-up otherwise.
+    pub fn drive(&self, ext: &mut ExtDrives) {
+        let switch = |low| DriveState::ground_switch(Some(low));
+
+        ext.rdiff = switch(self.right_difficulty == Difficulty::B);
+        ext.ldiff = switch(self.left_difficulty == Difficulty::B);
+        ext.col = switch(self.tv_type == TvType::BlackWhite);
+        ext.sel = switch(self.select_pressed());
+        ext.res = switch(self.reset_pressed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{line::error::ClockEdge, read::single::SingleRead, signal::LineSignal},
+        full::Emulator,
+    };
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, false)]
+    #[case(1, true)]
+    #[case(3, true)]
+    fn select_held(#[case] frames: u32, #[case] pressed: bool) {
+        let mut switches = ConsoleSwitches::new();
+        switches.press_select(frames);
+        assert_eq!(switches.select_pressed(), pressed);
+        assert!(!switches.reset_pressed());
+    }
+
+    #[rstest]
+    #[case(1, 0, true)]
+    #[case(1, 1, false)]
+    #[case(2, 1, true)]
+    #[case(2, 5, false)]
+    fn reset_released_after_frames(
+        #[case] frames: u32,
+        #[case] elapsed: u32,
+        #[case] pressed: bool,
+    ) {
+        let mut switches = ConsoleSwitches::new();
+        switches.press_reset(frames);
+        for _ in 0..elapsed {
+            switches.end_frame();
+        }
+        assert_eq!(switches.reset_pressed(), pressed);
+    }
+
+    #[test]
+    fn drive_levels() {
+        let mut switches = ConsoleSwitches::new();
+        switches.tv_type = TvType::BlackWhite;
+        switches.left_difficulty = Difficulty::A;
+        switches.press_reset(1);
+
+        let mut ext = ExtDrives::new();
+        switches.drive(&mut ext);
+
+        let low = DriveState::from(false);
+        let open = LineSignal::HighZ.into();
+        assert_eq!(ext.rdiff, low);
+        assert_eq!(ext.ldiff, open);
+        assert_eq!(ext.col, low);
+        assert_eq!(ext.sel, open);
+        assert_eq!(ext.res, low);
+    }
+
+    #[test]
+    fn released_switches_read_high_through_riot_pull_ups() {
+        let mut emu = Emulator::new();
+        let mut ext = ExtDrives::new();
+        ConsoleSwitches::new().drive(&mut ext);
+        emu.update(&ext, ClockEdge::Rising).unwrap();
+
+        assert_eq!(emu.line_states.sel, SingleRead::High);
+        assert_eq!(emu.line_states.rdiff, SingleRead::Low);
+    }
+}
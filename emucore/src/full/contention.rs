@@ -0,0 +1,350 @@
+use crate::{
+    common::{
+        codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer, varint_max_len},
+        line::{
+            error::{LineContext, LineError},
+            ident::LineIdent,
+            single::DriveState,
+        },
+        read::single::SingleRead,
+    },
+    full::line_reads::{LINE_COUNT, line_ident, line_index},
+};
+use arrayvec::ArrayVec;
+use core::hash::{Hash, Hasher};
+
+/// The number of contention events kept before further ones are only counted.
+pub const CONTENTION_LOG_LEN: usize = 64;
+
+/// What happens when drivers short a line.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ContentionPolicy {
+    /// Stop the tick with [`LineError::ShortCircuit`].
+    Error,
+
+    /// Read the line as Unknown and carry on.
+    Resolve,
+
+    /// Read the line as Unknown, carry on and record the event in the [`ContentionLog`].
+    ResolveAndLog,
+}
+
+impl Encode for ContentionPolicy {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        w.write_u8(match self {
+            Self::Error => 0,
+            Self::Resolve => 1,
+            Self::ResolveAndLog => 2,
+        })
+    }
+}
+
+impl Decode for ContentionPolicy {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        match r.read_u8()? {
+            0 => Ok(Self::Error),
+            1 => Ok(Self::Resolve),
+            2 => Ok(Self::ResolveAndLog),
+            _ => Err(CodecError::InvalidValue),
+        }
+    }
+}
+
+/// A short circuit which was resolved rather than reported.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ContentionEvent {
+    pub ident: LineIdent,
+    pub cycle: u64,
+}
+
+/// The line is stored by its index, as given by [`line_index`].
+impl Encode for ContentionEvent {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        let line = line_index(self.ident).ok_or(CodecError::InvalidValue)?;
+        w.write_varint(u64::try_from(line).map_err(|_| CodecError::InvalidValue)?)?;
+        w.write_varint(self.cycle)
+    }
+}
+
+impl MaxEncodedLen for ContentionEvent {
+    const MAX_ENCODED_LEN: usize = varint_max_len(u16::BITS) + varint_max_len(u64::BITS);
+}
+
+impl Decode for ContentionEvent {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let line = usize::try_from(r.read_varint()?).map_err(|_| CodecError::InvalidValue)?;
+
+        Ok(Self {
+            ident: line_ident(line).ok_or(CodecError::InvalidValue)?,
+            cycle: r.read_varint()?,
+        })
+    }
+}
+
+/// The first [`CONTENTION_LOG_LEN`] contention events, along with a count of the ones after.
+///
+/// Logs compare equal when their events and dropped counts do, whatever was dropped on the latest
+/// cycle.
+#[derive(Clone, Debug, Default)]
+pub struct ContentionLog {
+    events: ArrayVec<ContentionEvent, CONTENTION_LOG_LEN>,
+    dropped: u64,
+    /// The events dropped on the latest cycle, so each is only counted once.
+    dropped_now: ArrayVec<ContentionEvent, LINE_COUNT>,
+}
+
+impl ContentionLog {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            events: ArrayVec::new_const(),
+            dropped: 0,
+            dropped_now: ArrayVec::new_const(),
+        }
+    }
+
+    #[must_use]
+    pub fn events(&self) -> &[ContentionEvent] {
+        &self.events
+    }
+
+    /// The number of events which did not fit in the log.
+    #[must_use]
+    pub const fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.dropped = 0;
+        self.dropped_now.clear();
+    }
+
+    /// The length of the log, for [`ContentionLog::truncate`] to return to.
+    pub(crate) const fn mark(&self) -> (usize, u64) {
+        (self.events.len(), self.dropped)
+    }
+
+    /// Forgets the events recorded since `mark` was taken.
+    pub(crate) fn truncate(&mut self, (len, dropped): (usize, u64)) {
+        self.events.truncate(len);
+        self.dropped = dropped;
+        self.dropped_now.clear();
+    }
+
+    /// Records an event, skipping repeats of one already logged or counted, as a line is updated
+    /// more than once per cycle.
+    fn push(&mut self, event: ContentionEvent) {
+        if self.events.contains(&event) || self.dropped_now.contains(&event) {
+            return;
+        }
+
+        if self.events.try_push(event).is_err() {
+            if self
+                .dropped_now
+                .first()
+                .is_some_and(|e| e.cycle != event.cycle)
+            {
+                self.dropped_now.clear();
+            }
+            // Each line shorts at most once per cycle, so this never overflows
+            let _ = self.dropped_now.try_push(event);
+            self.dropped += 1;
+        }
+    }
+}
+
+impl PartialEq for ContentionLog {
+    fn eq(&self, other: &Self) -> bool {
+        self.events == other.events && self.dropped == other.dropped
+    }
+}
+
+impl Eq for ContentionLog {}
+
+impl Hash for ContentionLog {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.events.hash(state);
+        self.dropped.hash(state);
+    }
+}
+
+impl Encode for ContentionLog {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        w.write_varint(self.events.len() as u64)?;
+        for event in &self.events {
+            event.encode(w)?;
+        }
+
+        w.write_varint(self.dropped)
+    }
+}
+
+impl Decode for ContentionLog {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let mut res = Self::new();
+
+        for _ in 0..r.read_varint()? {
+            res.events
+                .try_push(Decode::decode(r)?)
+                .map_err(|_| CodecError::InvalidValue)?;
+        }
+
+        res.dropped = r.read_varint()?;
+        Ok(res)
+    }
+}
+
+/// The contention policy of an emulator, along with the events it has logged.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Contention {
+    pub policy: ContentionPolicy,
+    pub log: ContentionLog,
+}
+
+impl Default for Contention {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Contention {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            policy: ContentionPolicy::Error,
+            log: ContentionLog::new(),
+        }
+    }
+
+    /// Contends the drivers of a line, handling a short circuit as the policy says.
+    pub fn contend(
+        &mut self,
+        drives: impl Iterator<Item = DriveState>,
+        ident: LineIdent,
+        cycle: u64,
+    ) -> Result<DriveState, LineError> {
+        DriveState::contend(drives).map_or_else(|| self.short(ident, cycle), Ok)
+    }
+
+    /// Handles drivers shorting a line as the policy says.
+    pub fn short(&mut self, ident: LineIdent, cycle: u64) -> Result<DriveState, LineError> {
+        match self.policy {
+            ContentionPolicy::Error => {
+                return Err(LineError::ShortCircuit {
+                    ident,
+                    context: LineContext::default(),
+                });
+            }
+            ContentionPolicy::Resolve => (),
+            ContentionPolicy::ResolveAndLog => self.log.push(ContentionEvent { ident, cycle }),
+        }
+
+        Ok(SingleRead::Unknown.into())
+    }
+}
+
+impl Encode for Contention {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        self.policy.encode(w)?;
+        self.log.encode(w)
+    }
+}
+
+impl MaxEncodedLen for Contention {
+    const MAX_ENCODED_LEN: usize = 1
+        + varint_max_len(usize::BITS - CONTENTION_LOG_LEN.leading_zeros())
+        + CONTENTION_LOG_LEN * ContentionEvent::MAX_ENCODED_LEN
+        + varint_max_len(u64::BITS);
+}
+
+impl Decode for Contention {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        Ok(Self {
+            policy: Decode::decode(r)?,
+            log: Decode::decode(r)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logs_resolved_shorts() {
+        let ident = LineIdent::from("rw");
+        let short = || [true.into(), false.into()].into_iter();
+
+        let mut contention = Contention::new();
+        assert_eq!(
+            contention.contend(short(), ident, 0),
+            Err(LineError::ShortCircuit {
+                ident,
+                context: LineContext::default()
+            })
+        );
+
+        contention.policy = ContentionPolicy::ResolveAndLog;
+        for cycle in 0..=CONTENTION_LOG_LEN as u64 {
+            assert_eq!(
+                contention.contend(short(), ident, cycle),
+                Ok(SingleRead::Unknown.into())
+            );
+        }
+
+        assert_eq!(
+            contention.log.events()[1],
+            ContentionEvent { ident, cycle: 1 }
+        );
+        assert_eq!(contention.log.dropped(), 1);
+    }
+
+    #[test]
+    fn logs_each_short_once_per_cycle() {
+        let ident = LineIdent::from("rw");
+        let short = || [true.into(), false.into()].into_iter();
+
+        let mut contention = Contention::new();
+        contention.policy = ContentionPolicy::ResolveAndLog;
+        for cycle in [3, 3, 3, 3, 4] {
+            contention.contend(short(), ident, cycle).unwrap();
+        }
+
+        assert_eq!(
+            contention.log.events(),
+            [
+                ContentionEvent { ident, cycle: 3 },
+                ContentionEvent { ident, cycle: 4 }
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_each_dropped_short_once_per_cycle() {
+        let short = || [true.into(), false.into()].into_iter();
+        let mut contention = Contention::new();
+        contention.policy = ContentionPolicy::ResolveAndLog;
+
+        for cycle in 0..CONTENTION_LOG_LEN as u64 {
+            contention.contend(short(), "rw".into(), cycle).unwrap();
+        }
+
+        // Settling passes over both lines again and again.
+        for cycle in [100, 100, 101] {
+            for _ in 0..4 {
+                for ident in ["rw", "rdy"] {
+                    contention.contend(short(), ident.into(), cycle).unwrap();
+                }
+            }
+        }
+
+        assert_eq!(contention.log.events().len(), CONTENTION_LOG_LEN);
+        assert_eq!(contention.log.dropped(), 4);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{
+    common::{
+        cond::{IsCondition, base::BaseCondition},
+        read::single::SingleRead,
+    },
+    full::{
+        Emulator,
+        line_reads::{EmuLineStates, TIA_ADDR_LINES},
+        source::DriveSource,
+        tick::TickError,
+    },
+    riot::RAM_SIZE,
+};
+use arrayvec::ArrayVec;
+
+/// The number of divergence points kept per frame.
+pub const DIVERGENT_PCS_LEN: usize = 16;
+
+/// The Unknown bits left in a frame.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct FrameUnknowns {
+    pub frame: u64,
+    /// Unknown bits at the end of the frame in the RIOT RAM bytes the frame may have read.
+    pub ram: usize,
+    /// Unknown bits seen across every TIA write in the frame, counting an uncertain write as one.
+    pub tia_writes: usize,
+    /// The program counter at the end of the frame, if known.
+    pub pc: Option<u16>,
+    /// The first [`DIVERGENT_PCS_LEN`] distinct program counters in the frame after which the
+    /// program counter stopped being fully known, which are where the code path split.
+    pub divergent_pcs: ArrayVec<u16, DIVERGENT_PCS_LEN>,
+}
+
+impl FrameUnknowns {
+    #[must_use]
+    pub const fn is_deterministic(&self) -> bool {
+        self.ram == 0 && self.tia_writes == 0
+    }
+}
+
+fn tia_write_unknowns(lines: &EmuLineStates) -> usize {
+    let unknowns = |reads: &[SingleRead]| {
+        reads
+            .iter()
+            .filter(|&&read| read == SingleRead::Unknown)
+            .count()
+    };
+
+    match lines.tia_write() {
+        BaseCondition::No => 0,
+        BaseCondition::Yes => unknowns(&lines.a[..TIA_ADDR_LINES]) + unknowns(&lines.db[..]),
+        BaseCondition::Unknown => {
+            1 + unknowns(&lines.a[..TIA_ADDR_LINES]) + unknowns(&lines.db[..])
+        }
+    }
+}
+
+/// Marks the RIOT RAM bytes the bus may be reading from in `read`, one bit per address.
+fn mark_ram_reads(lines: &EmuLineStates, read: &mut u128) {
+    let r = lines.riot_reads();
+    let ram_read = r.cs1.as_cond() & !r.cs2.as_cond() & !r.rs.as_cond() & r.rw.as_cond();
+
+    if ram_read != BaseCondition::No {
+        for addr in r.a.iter_possible_reads() {
+            *read |= 1 << addr;
+        }
+    }
+}
+
+impl Emulator {
+    /// Runs until a frame in which every TIA write and all of RIOT RAM are fully known, reporting
+    /// the Unknown bits left in each frame along the way.
+    ///
+    /// Returns the first such frame, or `None` if none was found within `max_frames` frames. This
+    /// is meant to be run from the all-Unknown power-on state given by [`Emulator::new`].
+    pub fn time_to_determinism(
+        &mut self,
+        source: &mut impl DriveSource,
+        max_frames: u64,
+        mut on_frame: impl FnMut(&FrameUnknowns),
+    ) -> Result<Option<FrameUnknowns>, TickError> {
+        let mut pc = self.known_pc();
+
+        for _ in 0..max_frames {
+            let frame = self.frame();
+            let mut tia_writes = 0;
+            let mut ram_read = 0;
+            let mut divergent_pcs = ArrayVec::new();
+
+            while self.frame() == frame {
+                let ext = source.drives(self);
+                self.tick(&ext)?;
+                tia_writes += tia_write_unknowns(&self.line_states);
+                mark_ram_reads(&self.line_states, &mut ram_read);
+
+                let next_pc = self.known_pc();
+                if let (Some(last), None) = (pc, next_pc)
+                    && !divergent_pcs.contains(&last)
+                {
+                    let _ = divergent_pcs.try_push(last);
+                }
+                pc = next_pc;
+            }
+
+            let unknowns = FrameUnknowns {
+                frame,
+                ram: (0..RAM_SIZE)
+                    .filter(|addr| ram_read >> addr & 1 == 1)
+                    .flat_map(|addr| self.riot.ram()[addr].iter())
+                    .filter(|&&read| read == SingleRead::Unknown)
+                    .count(),
+                tia_writes,
+                pc,
+                divergent_pcs,
+            };
+
+            on_frame(&unknowns);
+            if unknowns.is_deterministic() {
+                return Ok(Some(unknowns));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::read::multi::MultiRead;
+
+    #[test]
+    fn counts_tia_write_unknowns() {
+        let mut lines = EmuLineStates::new();
+        lines.a = MultiRead::from_value(0x0009);
+        lines.db = MultiRead::from_value(0x42);
+        lines.rw = SingleRead::High;
+        assert_eq!(tia_write_unknowns(&lines), 0);
+
+        lines.rw = SingleRead::Low;
+        lines.db[3] = SingleRead::Unknown;
+        assert_eq!(tia_write_unknowns(&lines), 1);
+
+        lines.a[12] = SingleRead::Unknown;
+        lines.a[0] = SingleRead::Unknown;
+        assert_eq!(tia_write_unknowns(&lines), 3);
+    }
+
+    #[test]
+    fn marks_possible_ram_reads() {
+        let mut lines = EmuLineStates::new();
+        lines.a = MultiRead::from_value(0x0805);
+        lines.rw = SingleRead::High;
+        let mut read = 0;
+
+        mark_ram_reads(&lines, &mut read);
+        assert_eq!(read, 1 << 0x05);
+
+        lines.a[1] = SingleRead::Unknown;
+        mark_ram_reads(&lines, &mut read);
+        assert_eq!(read, 1 << 0x05 | 1 << 0x07);
+
+        // A write to RAM, or a read of the RIOT registers, does not count.
+        read = 0;
+        lines.rw = SingleRead::Low;
+        mark_ram_reads(&lines, &mut read);
+        lines.rw = SingleRead::High;
+        lines.a = MultiRead::from_value(0x0a05);
+        mark_ram_reads(&lines, &mut read);
+        assert_eq!(read, 0);
+    }
+}
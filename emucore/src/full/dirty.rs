@@ -0,0 +1,291 @@
+use crate::{
+    common::{
+        line::{
+            error::{LineDriver, MAX_LINE_DRIVERS},
+            ident::LineIdent,
+            single::DriveState,
+        },
+        read::single::SingleRead,
+    },
+    full::{
+        bus_hold::BusHoldState,
+        line_reads::{BUSES, LINE_COUNT, MAX_NET_DRIVES, NET_COUNT, NETS, line_index},
+    },
+};
+use arrayvec::ArrayVec;
+use core::{
+    array,
+    hash::{Hash, Hasher},
+};
+
+/// The number of bits [`DriveState::to_bits`] packs a drive into.
+const DRIVE_BITS: usize = 5;
+
+const _: () = assert!(
+    MAX_LINE_DRIVERS * DRIVE_BITS <= u32::BITS as usize,
+    "the drives of a line must fit in a cache key"
+);
+
+/// The width of the widest net.
+const MAX_NET_WIDTH: usize = {
+    let mut width = 1;
+    let mut i = 0;
+    while i < BUSES.len() {
+        if BUSES[i].1 > width {
+            width = BUSES[i].1;
+        }
+        i += 1;
+    }
+    width
+};
+
+/// Every drive feeding a net, packed with [`DriveState::to_bits`] in the order of its pins.
+pub type NetDrives = ArrayVec<u8, MAX_NET_DRIVES>;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct CachedLine {
+    drives: u32,
+    read: SingleRead,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct CachedNet {
+    drives: NetDrives,
+    reads: ArrayVec<SingleRead, MAX_NET_WIDTH>,
+}
+
+/// The drives each line was last resolved from, so a line whose drivers have not changed since
+/// keeps its read rather than being contended again.
+///
+/// Whole nets are cached too, so a net none of whose drivers have changed is skipped without
+/// looking at its lines. Only lines which resolved cleanly without bus-hold are cached, as shorts
+/// are logged per cycle and held values age. The cache only saves work, so it never affects
+/// equality or hashing.
+#[derive(Clone, Debug)]
+pub struct DriveCache {
+    lines: [Option<CachedLine>; LINE_COUNT],
+    nets: [Option<CachedNet>; NET_COUNT],
+}
+
+impl Default for DriveCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DriveCache {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            lines: [None; _],
+            nets: array::from_fn(|_| None),
+        }
+    }
+
+    fn key(drivers: &[(LineDriver, DriveState)]) -> u32 {
+        drivers.iter().fold(0, |key, &(_, drive)| {
+            key << DRIVE_BITS | u32::from(drive.to_bits())
+        })
+    }
+
+    /// Whether a line still reads `current` from the same drivers it was last resolved from.
+    #[must_use]
+    pub fn is_clean(
+        &self,
+        hold: &BusHoldState,
+        ident: LineIdent,
+        drivers: &[(LineDriver, DriveState)],
+        current: SingleRead,
+    ) -> bool {
+        hold.get(ident.net_name()).is_none()
+            && line_index(ident).and_then(|i| self.lines[i])
+                == Some(CachedLine {
+                    drives: Self::key(drivers),
+                    read: current,
+                })
+    }
+
+    /// Records the read a line resolved to from its drivers.
+    pub fn insert(
+        &mut self,
+        ident: LineIdent,
+        drivers: &[(LineDriver, DriveState)],
+        read: SingleRead,
+    ) {
+        if let Some(i) = line_index(ident) {
+            self.lines[i] = Some(CachedLine {
+                drives: Self::key(drivers),
+                read,
+            });
+        }
+    }
+
+    /// Whether every line of the `net`th net still reads `current` from the same drives it was
+    /// last resolved from.
+    #[must_use]
+    pub fn is_net_clean(
+        &self,
+        hold: &BusHoldState,
+        net: usize,
+        drives: &NetDrives,
+        current: &[SingleRead],
+    ) -> bool {
+        hold.get(NETS[net].name).is_none()
+            && self.nets[net].as_ref().is_some_and(|cached| {
+                cached.drives == *drives && cached.reads.as_slice() == current
+            })
+    }
+
+    /// Records the reads of the `net`th net if every line resolved cleanly, or forgets them.
+    pub fn set_net(&mut self, net: usize, drives: NetDrives, reads: Option<&[SingleRead]>) {
+        self.nets[net] = reads.map(|reads| CachedNet {
+            drives,
+            reads: reads.iter().copied().collect(),
+        });
+    }
+}
+
+impl PartialEq for DriveCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for DriveCache {}
+
+impl Hash for DriveCache {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{line::error::ClockEdge, rng::SplitMix64, signal::LineSignal},
+        full::{Emulator, bus_hold::BusHold, contention::ContentionPolicy, ext_drives::ExtDrives},
+    };
+
+    fn pick<T: Copy>(rng: &mut SplitMix64, items: &[T]) -> T {
+        items[usize::try_from(rng.next_u64() % items.len() as u64).unwrap()]
+    }
+
+    /// A drive which is mostly released or a single signal, so most lines resolve but some short.
+    fn random_drive(rng: &mut SplitMix64) -> DriveState {
+        if rng.next_u64().is_multiple_of(8) {
+            let bits = u8::try_from(rng.next_u64() % 31 + 1).unwrap();
+            DriveState::from_bits(bits).unwrap()
+        } else {
+            pick(
+                rng,
+                &[
+                    LineSignal::HighZ,
+                    LineSignal::HighZ,
+                    LineSignal::HighZ,
+                    LineSignal::Low,
+                    LineSignal::High,
+                    LineSignal::WeakLow,
+                    LineSignal::WeakHigh,
+                ],
+            )
+            .into()
+        }
+    }
+
+    fn ext_drives_mut(ext: &mut ExtDrives) -> impl Iterator<Item = &mut DriveState> {
+        ext.a
+            .iter_mut()
+            .chain(ext.db.iter_mut())
+            .chain(ext.inp1.iter_mut())
+            .chain(ext.inp2.iter_mut())
+            .chain([
+                &mut ext.rdiff,
+                &mut ext.ldiff,
+                &mut ext.col,
+                &mut ext.sel,
+                &mut ext.res,
+            ])
+    }
+
+    /// Changes a few drives and settings, as a run would between updates.
+    fn perturb(rng: &mut SplitMix64, emu: &mut Emulator, ext: &mut ExtDrives) {
+        let drives = emu
+            .cpu
+            .drives_mut()
+            .chain(emu.riot.drives_mut())
+            .chain(ext_drives_mut(ext));
+        for drive in drives {
+            if rng.next_u64().is_multiple_of(16) {
+                *drive = random_drive(rng);
+            }
+        }
+
+        let net = pick(rng, &NETS);
+        match rng.next_u64() % 8 {
+            0 => {
+                let hold = pick(
+                    rng,
+                    &[
+                        None,
+                        Some(BusHold { decay: None }),
+                        Some(BusHold { decay: Some(1) }),
+                        Some(BusHold { decay: Some(3) }),
+                    ],
+                );
+                emu.set_bus_hold(net.name, hold);
+            }
+            1 => emu.set_contention_policy(pick(
+                rng,
+                &[
+                    ContentionPolicy::Error,
+                    ContentionPolicy::Resolve,
+                    ContentionPolicy::ResolveAndLog,
+                ],
+            )),
+            2 => {
+                let lines = emu.line_states.net_mut(net.name).unwrap();
+                let bit = pick(rng, &[0, 1, 2, 3, 4, 5, 6]) % lines.len();
+                lines[bit] = pick(
+                    rng,
+                    &[SingleRead::Low, SingleRead::High, SingleRead::Unknown],
+                );
+            }
+            3 => emu.cycles += 1,
+            _ => (),
+        }
+
+        if rng.next_u64().is_multiple_of(2) {
+            emu.line_states.hold.advance_half_cycle();
+        }
+    }
+
+    #[test]
+    fn matches_uncached_update() {
+        let mut rng = SplitMix64::new(0x5eed);
+        let mut emu = Emulator::new();
+        let mut ext = ExtDrives::new();
+        emu.set_contention_policy(ContentionPolicy::ResolveAndLog);
+
+        for step in 0..4000 {
+            perturb(&mut rng, &mut emu, &mut ext);
+
+            let mut uncached = emu.clone();
+            uncached.line_states.cache = DriveCache::new();
+
+            let edge = pick(&mut rng, &[ClockEdge::Rising, ClockEdge::Falling]);
+            assert_eq!(
+                emu.update(&ext, edge),
+                uncached.update(&ext, edge),
+                "step {step}"
+            );
+            for net in &NETS {
+                assert_eq!(
+                    emu.line_states.net(net.name),
+                    uncached.line_states.net(net.name),
+                    "net {} on step {step}",
+                    net.name
+                );
+            }
+            assert_eq!(emu, uncached, "step {step}");
+        }
+    }
+}
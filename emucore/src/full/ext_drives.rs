@@ -1,7 +1,14 @@
-use crate::common::line::{multi::BusDriveState, single::DriveState};
+use crate::common::{
+    codec::{CodecError, Decode, Encode, Reader, Writer},
+    combine::Combine,
+    line::{multi::BusDriveState, single::DriveState},
+    signal::LineSignal,
+};
 
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct ExtDrives {
     pub a: BusDriveState<13>,
+    /// The cartridge's drive on the data bus.
     pub db: BusDriveState<8>,
     pub inp1: BusDriveState<7>,
     pub inp2: BusDriveState<7>,
@@ -11,3 +18,74 @@ pub struct ExtDrives {
     pub sel: DriveState,
     pub res: DriveState,
 }
+
+impl Default for ExtDrives {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExtDrives {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            a: BusDriveState::from_signals(&[LineSignal::HighZ; _]),
+            db: BusDriveState::from_signals(&[LineSignal::HighZ; _]),
+            inp1: BusDriveState::from_signals(&[LineSignal::HighZ; _]),
+            inp2: BusDriveState::from_signals(&[LineSignal::HighZ; _]),
+            rdiff: LineSignal::HighZ.into(),
+            ldiff: LineSignal::HighZ.into(),
+            col: LineSignal::HighZ.into(),
+            sel: LineSignal::HighZ.into(),
+            res: LineSignal::HighZ.into(),
+        }
+    }
+}
+
+impl Encode for ExtDrives {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        self.a.encode(w)?;
+        self.db.encode(w)?;
+        self.inp1.encode(w)?;
+        self.inp2.encode(w)?;
+        BusDriveState::from([self.rdiff, self.ldiff, self.col, self.sel, self.res]).encode(w)
+    }
+}
+
+impl Decode for ExtDrives {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let a = BusDriveState::decode(r)?;
+        let db = BusDriveState::decode(r)?;
+        let inp1 = BusDriveState::decode(r)?;
+        let inp2 = BusDriveState::decode(r)?;
+        let BusDriveState([rdiff, ldiff, col, sel, res]) = BusDriveState::decode(r)?;
+
+        Ok(Self {
+            a,
+            db,
+            inp1,
+            inp2,
+            rdiff,
+            ldiff,
+            col,
+            sel,
+            res,
+        })
+    }
+}
+
+impl Combine for ExtDrives {
+    fn combine_with(&self, other: &Self) -> Self {
+        Self {
+            a: self.a.combine_with(&other.a),
+            db: self.db.combine_with(&other.db),
+            inp1: self.inp1.combine_with(&other.inp1),
+            inp2: self.inp2.combine_with(&other.inp2),
+            rdiff: self.rdiff.combine_with(&other.rdiff),
+            ldiff: self.ldiff.combine_with(&other.ldiff),
+            col: self.col.combine_with(&other.col),
+            sel: self.sel.combine_with(&other.sel),
+            res: self.res.combine_with(&other.res),
+        }
+    }
+}
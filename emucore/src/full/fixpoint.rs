@@ -0,0 +1,134 @@
+use crate::{
+    common::combine::Combine,
+    full::{Emulator, bits::StateBit, source::DriveSource, tick::TickError},
+};
+use thiserror::Error;
+
+#[derive(Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum FixpointError {
+    #[error("cannot run frame: {0}")]
+    Tick(#[from] TickError),
+
+    #[error("cannot find fixpoint as it did not converge within {iterations} iterations")]
+    NotConverged { iterations: u32 },
+}
+
+/// A state which covers the emulator at every frame boundary of a run.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Fixpoint {
+    pub state: Emulator,
+    pub iterations: u32,
+}
+
+impl Fixpoint {
+    /// Iterates over the bits which hold the same value at every frame boundary.
+    pub fn known_bits(&self) -> impl Iterator<Item = (StateBit, bool)> + '_ {
+        self.state
+            .bits()
+            .filter_map(|(bit, read)| Some((bit, read.as_bool()?)))
+    }
+}
+
+impl Emulator {
+    /// Finds a frame-level invariant by running frames from an accumulated state and joining each
+    /// result into it, until the accumulated state covers the frame after it.
+    ///
+    /// The accumulated state starts at the next frame boundary. For the result to hold for every
+    /// run, `source` should supply drives covering every input the console could see, such as
+    /// Unknown controller inputs.
+    pub fn frame_fixpoint(
+        &self,
+        source: &mut impl DriveSource,
+        max_iterations: u32,
+    ) -> Result<Fixpoint, FixpointError> {
+        let mut acc = self.clone();
+        if !acc.beam.is_frame_start() {
+            acc.run_frame(source)?;
+        }
+
+        acc.fixpoint(max_iterations, |emu| emu.run_frame(source))
+    }
+
+    /// Joins the states `step` reaches into `self` until they are covered. The accumulated state
+    /// takes the timing of the latest step, so sources keyed on the frame see the current one.
+    fn fixpoint(
+        self,
+        max_iterations: u32,
+        mut step: impl FnMut(&mut Self) -> Result<(), TickError>,
+    ) -> Result<Fixpoint, FixpointError> {
+        let mut acc = self;
+
+        for iterations in 1..=max_iterations {
+            let mut next = acc.clone();
+            step(&mut next)?;
+
+            if acc.covers(&next) {
+                return Ok(Fixpoint {
+                    state: acc,
+                    iterations,
+                });
+            }
+
+            acc = next.combine_with(&acc);
+        }
+
+        Err(FixpointError::NotConverged {
+            iterations: max_iterations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::read::{multi::MultiRead, single::SingleRead};
+
+    fn next_frame(emu: &mut Emulator) -> u64 {
+        let frame = emu.frame();
+        while emu.frame() == frame {
+            emu.beam.advance_cycle();
+        }
+        emu.frame()
+    }
+
+    fn zeroed() -> Emulator {
+        let mut emu = Emulator::new();
+        for byte in &mut emu.riot.ram_mut()[..8] {
+            *byte = MultiRead::from_value(0);
+        }
+        emu
+    }
+
+    #[test]
+    fn converges_on_alternating_frames() {
+        let mut emu = zeroed();
+        emu.riot.ram_mut()[0] = MultiRead::from_value(0x10);
+
+        let fixpoint = emu
+            .fixpoint(8, |emu| {
+                let frame = next_frame(emu);
+                emu.riot.ram_mut()[0] = MultiRead::from_value(0x10 | u16::from(frame % 2 == 1));
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(fixpoint.iterations, 2);
+        assert_eq!(fixpoint.state.frame(), 1);
+
+        let byte = &fixpoint.state.riot.ram()[0];
+        assert_eq!(byte[0], SingleRead::Unknown);
+        assert_eq!(byte[4], SingleRead::High);
+        assert_eq!(byte[1], SingleRead::Low);
+    }
+
+    #[test]
+    fn stops_at_the_cap() {
+        let result = zeroed().fixpoint(3, |emu| {
+            let frame = next_frame(emu);
+            emu.riot.ram_mut()[usize::try_from(frame).unwrap()] = MultiRead::from_value(0xff);
+            Ok(())
+        });
+
+        assert_eq!(result, Err(FixpointError::NotConverged { iterations: 3 }));
+    }
+}
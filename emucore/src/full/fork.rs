@@ -0,0 +1,143 @@
+use crate::{
+    common::read::single::SingleRead,
+    full::{Emulator, bits::StateBit},
+};
+use arrayvec::ArrayVec;
+use thiserror::Error;
+
+/// The most bits a single fork can split on, giving up to `2^MAX_FORK_BITS` branches.
+pub const MAX_FORK_BITS: usize = 16;
+
+/// The concrete values assumed for the split bits of a branch.
+pub type Assumption = ArrayVec<(StateBit, bool), MAX_FORK_BITS>;
+
+#[derive(Clone, Copy, Debug, Eq, Error, Hash, PartialEq)]
+pub enum ForkError {
+    #[error("cannot fork on {bit} as it does not exist")]
+    InvalidBit { bit: StateBit },
+
+    #[error("cannot fork on {bit} as lines are recomputed from their drivers on every update")]
+    LineBit { bit: StateBit },
+
+    #[error("cannot fork on more than {MAX_FORK_BITS} unknown bits")]
+    TooManyBits,
+}
+
+/// One concrete branch of a forked emulator.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Fork {
+    pub emulator: Emulator,
+    pub assumption: Assumption,
+}
+
+/// The branches of a forked emulator, produced lazily.
+pub struct Forks {
+    base: Emulator,
+    split: ArrayVec<StateBit, MAX_FORK_BITS>,
+    next: u32,
+}
+
+impl Iterator for Forks {
+    type Item = Fork;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= 1 << self.split.len() {
+            return None;
+        }
+
+        let mut emulator = self.base.clone();
+        let mut assumption = Assumption::new();
+
+        for (i, &bit) in self.split.iter().enumerate() {
+            let value = self.next >> i & 1 == 1;
+            if let Some(read) = emulator.bit_mut(bit) {
+                *read = SingleRead::from(value);
+            }
+            assumption.push((bit, value));
+        }
+
+        self.next += 1;
+        Some(Fork {
+            emulator,
+            assumption,
+        })
+    }
+}
+
+impl Emulator {
+    /// Splits the emulator into one branch per concrete value of the given bits.
+    ///
+    /// Only bits which are currently Unknown are split on, so bits which are already known are
+    /// left as they are and do not appear in the assumptions. Line bits are rejected, as the next
+    /// update would overwrite them; fork on the registers driving the line instead.
+    pub fn fork(&self, bits: &[StateBit]) -> Result<Forks, ForkError> {
+        let mut split = ArrayVec::new();
+
+        for &bit in bits {
+            if matches!(bit, StateBit::Line { .. }) {
+                return Err(ForkError::LineBit { bit });
+            }
+
+            let read = self.bit(bit).ok_or(ForkError::InvalidBit { bit })?;
+
+            if read == SingleRead::Unknown && !split.contains(&bit) {
+                split.try_push(bit).map_err(|_| ForkError::TooManyBits)?;
+            }
+        }
+
+        Ok(Forks {
+            base: self.clone(),
+            split,
+            next: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{common::combine::Combine, cpu::regs::CpuReg};
+
+    #[test]
+    fn forks_unknown_bits() {
+        let mut emu = Emulator::new();
+        *emu.bit_mut(StateBit::Cpu {
+            reg: CpuReg::A,
+            bit: 1,
+        })
+        .unwrap() = SingleRead::High;
+
+        let bits: ArrayVec<_, 8> = StateBit::cpu_reg(CpuReg::A).take(3).collect();
+        let forks: ArrayVec<_, 4> = emu.fork(&bits).unwrap().collect();
+
+        assert_eq!(forks.len(), 4);
+        for fork in &forks {
+            assert_eq!(fork.assumption.len(), 2);
+            for &(bit, value) in &fork.assumption {
+                assert_eq!(fork.emulator.bit(bit), Some(SingleRead::from(value)));
+            }
+        }
+
+        let joined = forks
+            .iter()
+            .map(|fork| fork.emulator.clone())
+            .reduce(|acc, emu| acc.combine_with(&emu))
+            .unwrap();
+        assert_eq!(joined, emu);
+    }
+
+    #[test]
+    fn rejects_invalid_bit() {
+        let bit = StateBit::Ram { addr: 200, bit: 0 };
+        assert!(matches!(
+            Emulator::new().fork(&[bit]),
+            Err(ForkError::InvalidBit { .. })
+        ));
+
+        let bit = StateBit::Line { ident: "rw".into() };
+        assert_eq!(
+            Emulator::new().fork(&[bit]).err(),
+            Some(ForkError::LineBit { bit })
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{
+    common::{cond::base::BaseCondition, read::single::SingleRead},
+    full::{
+        Emulator,
+        bits::StateBit,
+        line_reads::{EmuLineStates, TIA_ADDR_LINES},
+        source::{DriveSource, UnknownInputs},
+        tick::TickError,
+    },
+    riot::RAM_SIZE,
+};
+use arrayvec::ArrayVec;
+use core::ops::Range;
+
+/// The number of input-dependent TIA writes kept before further ones are only counted.
+pub const DEPENDENT_TIA_WRITES_LEN: usize = 64;
+
+/// Whether a TIA write definitely took place with a known register and data, along with the
+/// register.
+fn known_tia_write(lines: &EmuLineStates) -> Option<u8> {
+    let known = lines.tia_write() == BaseCondition::Yes
+        && lines.a[..TIA_ADDR_LINES]
+            .iter()
+            .chain(lines.db.iter())
+            .all(|read| read.as_bool().is_some());
+
+    known
+        .then(|| lines.a.iter_possible_reads().next())
+        .flatten()
+        .map(|addr| (addr & ((1 << TIA_ADDR_LINES) - 1)) as u8)
+}
+
+/// A TIA write which took place with known inputs, but not for certain with Unknown inputs.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DependentTiaWrite {
+    pub cycle: u64,
+    /// The register written with concrete inputs.
+    pub reg: u8,
+    /// Whether the write itself or the register it selects depended on the inputs.
+    pub addr: bool,
+    /// Whether the value written depended on the inputs.
+    pub data: bool,
+}
+
+impl DependentTiaWrite {
+    fn new(cycle: u64, reg: u8, symbolic: &EmuLineStates) -> Option<Self> {
+        let addr = symbolic.tia_write() != BaseCondition::Yes
+            || symbolic.a[..TIA_ADDR_LINES].contains(&SingleRead::Unknown);
+        let data = symbolic.db.contains(&SingleRead::Unknown);
+
+        (addr || data).then_some(Self {
+            cycle,
+            reg,
+            addr,
+            data,
+        })
+    }
+}
+
+/// Which parts of the emulator depended on the player's inputs, found by running with concrete
+/// inputs and with Unknown inputs side by side.
+///
+/// A bit only counts as input-dependent if it is known in the concrete run, so bits left Unknown
+/// from power-on are never reported.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct InputDependence {
+    pub concrete: Emulator,
+    pub symbolic: Emulator,
+    /// The first [`DEPENDENT_TIA_WRITES_LEN`] TIA writes which depended on the inputs.
+    pub tia_writes: ArrayVec<DependentTiaWrite, DEPENDENT_TIA_WRITES_LEN>,
+    /// The number of input-dependent TIA writes which did not fit in `tia_writes`.
+    pub dropped_tia_writes: u64,
+    ram: [u8; RAM_SIZE],
+}
+
+impl InputDependence {
+    /// Whether `bit` depended on the inputs. RAM bits count if they did at the end of any frame,
+    /// and every other bit counts if it does at the end of the run.
+    #[must_use]
+    pub fn depends_on_input(&self, bit: StateBit) -> bool {
+        if let StateBit::Ram { addr, bit } = bit {
+            return self.ram.get(addr).is_some_and(|mask| mask >> bit & 1 == 1);
+        }
+
+        let concrete = self.concrete.bit(bit).and_then(SingleRead::as_bool);
+        let symbolic = self.symbolic.bit(bit);
+        concrete.is_some() && symbolic == Some(SingleRead::Unknown)
+    }
+
+    /// Iterates over the RAM bits which depended on the inputs.
+    pub fn dependent_ram_bits(&self) -> impl Iterator<Item = StateBit> + '_ {
+        (0..RAM_SIZE)
+            .flat_map(StateBit::ram_byte)
+            .filter(|&bit| self.depends_on_input(bit))
+    }
+
+    fn record_tia_write(&mut self) {
+        let Some(reg) = known_tia_write(&self.concrete.line_states) else {
+            return;
+        };
+
+        if let Some(write) =
+            DependentTiaWrite::new(self.concrete.cycles, reg, &self.symbolic.line_states)
+            && self.tia_writes.try_push(write).is_err()
+        {
+            self.dropped_tia_writes += 1;
+        }
+    }
+
+    fn record_ram(&mut self) {
+        let (concrete, symbolic) = (self.concrete.riot.ram(), self.symbolic.riot.ram());
+
+        for (addr, mask) in self.ram.iter_mut().enumerate() {
+            for (bit, (c, s)) in concrete[addr].iter().zip(symbolic[addr].iter()).enumerate() {
+                if c.as_bool().is_some() && *s == SingleRead::Unknown {
+                    *mask |= 1 << bit;
+                }
+            }
+        }
+    }
+}
+
+impl Emulator {
+    /// Runs until `end_frame` with the drives from `source`, alongside a copy whose joysticks read
+    /// as Unknown during `unknown_frames`, and reports what depended on the joysticks.
+    pub fn input_dependence(
+        &self,
+        source: &mut impl DriveSource,
+        unknown_frames: Range<u64>,
+        end_frame: u64,
+    ) -> Result<InputDependence, TickError> {
+        let mut res = InputDependence {
+            concrete: self.clone(),
+            symbolic: self.clone(),
+            tia_writes: ArrayVec::new(),
+            dropped_tia_writes: 0,
+            ram: [0; _],
+        };
+
+        while res.concrete.frame() < end_frame {
+            let frame = res.concrete.frame();
+            let ext = source.drives(&res.concrete);
+            let mut unknown = UnknownInputs {
+                source: ext.clone(),
+                frames: unknown_frames.clone(),
+            };
+
+            res.symbolic.tick(&unknown.drives(&res.symbolic))?;
+            res.concrete.tick(&ext)?;
+
+            res.record_tia_write();
+
+            if res.concrete.frame() != frame {
+                res.record_ram();
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::read::multi::MultiRead;
+
+    fn dependence(concrete: Emulator, symbolic: Emulator) -> InputDependence {
+        InputDependence {
+            concrete,
+            symbolic,
+            tia_writes: ArrayVec::new(),
+            dropped_tia_writes: 0,
+            ram: [0; _],
+        }
+    }
+
+    #[test]
+    fn reports_ram_which_became_unknown() {
+        let mut concrete = Emulator::new();
+        concrete.riot.ram_mut()[4] = MultiRead::from_value(0);
+        let mut symbolic = concrete.clone();
+        symbolic.riot.ram_mut()[4][2] = SingleRead::Unknown;
+        symbolic.riot.ram_mut()[5][0] = SingleRead::Unknown;
+
+        let mut dependence = dependence(concrete, symbolic);
+        dependence.record_ram();
+
+        assert!(
+            dependence
+                .dependent_ram_bits()
+                .eq([StateBit::Ram { addr: 4, bit: 2 }])
+        );
+    }
+
+    #[test]
+    fn reports_tia_writes_which_depend_on_input() {
+        let mut concrete = Emulator::new();
+        concrete.cycles = 7;
+        concrete.line_states.a = MultiRead::from_value(0x0009);
+        concrete.line_states.rw = SingleRead::Low;
+        concrete.line_states.db = MultiRead::from_value(0x42);
+
+        let mut dependence = dependence(concrete.clone(), concrete);
+        dependence.record_tia_write();
+        assert!(dependence.tia_writes.is_empty());
+
+        dependence.symbolic.line_states.db[1] = SingleRead::Unknown;
+        dependence.record_tia_write();
+        dependence.symbolic.line_states.a[0] = SingleRead::Unknown;
+        dependence.record_tia_write();
+
+        let write = |addr, data| DependentTiaWrite {
+            cycle: 7,
+            reg: 0x09,
+            addr,
+            data,
+        };
+        assert_eq!(
+            dependence.tia_writes.as_slice(),
+            [write(false, true), write(true, true)]
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use crate::common::{
+    line::{multi::BusDriveState, single::DriveState},
+    signal::LineSignal,
+};
+
+const UP_PIN: usize = 0;
+const DOWN_PIN: usize = 1;
+const LEFT_PIN: usize = 2;
+const RIGHT_PIN: usize = 3;
+const POT_B_PIN: usize = 4;
+const FIRE_PIN: usize = 5;
+const POT_A_PIN: usize = 6;
+
+/// A standard joystick plugged into one of the controller ports.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Joystick {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub fire: bool,
+}
+
+impl Joystick {
+    /// Drives the data pins of a controller port. Each switch grounds its pin while closed and
+    /// leaves it to the pull-ups while open.
+    pub fn drive(&self, inp: &mut BusDriveState<7>) {
+        for (pin, pressed) in [
+            (UP_PIN, self.up),
+            (DOWN_PIN, self.down),
+            (LEFT_PIN, self.left),
+            (RIGHT_PIN, self.right),
+            (FIRE_PIN, self.fire),
+        ] {
+            inp[pin] = DriveState::ground_switch(Some(pressed));
+        }
+
+        inp[POT_A_PIN] = LineSignal::HighZ.into();
+        inp[POT_B_PIN] = LineSignal::HighZ.into();
+    }
+
+    /// Drives the data pins of a controller port as if every switch could be open or closed.
+    pub fn drive_unknown(inp: &mut BusDriveState<7>) {
+        for pin in [UP_PIN, DOWN_PIN, LEFT_PIN, RIGHT_PIN, FIRE_PIN] {
+            inp[pin] = DriveState::ground_switch(None);
+        }
+
+        inp[POT_A_PIN] = LineSignal::HighZ.into();
+        inp[POT_B_PIN] = LineSignal::HighZ.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{line::error::ClockEdge, read::single::SingleRead},
+        full::{Emulator, ext_drives::ExtDrives},
+    };
+
+    #[test]
+    fn switches_only_pull_against_port_outputs() {
+        let mut emu = Emulator::new();
+        for pin in 4..8 {
+            emu.riot.pa_out[pin] = false.into();
+        }
+
+        let mut ext = ExtDrives::new();
+        Joystick::default().drive(&mut ext.inp1);
+        Joystick::drive_unknown(&mut ext.inp2);
+        emu.update(&ext, ClockEdge::Rising).unwrap();
+
+        // The port drives P1's pins low and leaves P2's to its pull-ups.
+        assert!(
+            emu.line_states.inp1[..4]
+                .iter()
+                .all(|&read| read == SingleRead::Low)
+        );
+        assert_eq!(emu.line_states.inp2[0], SingleRead::Unknown);
+    }
+}
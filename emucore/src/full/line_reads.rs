@@ -1,152 +1,264 @@
 use crate::{
     common::{
-        line::{error::LineError, ident::LineIdent, multi::BusDriveState, single::DriveState},
+        codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer},
+        combine::Combine,
+        cond::{IsCondition, base::BaseCondition, check::CheckIs},
+        line::{
+            error::{ClockEdge, DriverState, LineContext, LineDriver, LineError, MAX_LINE_DRIVERS},
+            ident::LineIdent,
+            single::DriveState,
+        },
         read::{multi::MultiRead, single::SingleRead},
     },
     cpu::{Cpu, reads::CpuLineReads},
-    full::ext_drives::ExtDrives,
+    full::{
+        beam::Beam,
+        bus_hold::BusHoldState,
+        contention::Contention,
+        dirty::{DriveCache, NetDrives},
+        ext_drives::ExtDrives,
+        netlist::{Net, Pin, PinDirection},
+        settle::NetSet,
+        wiring::{PinDrives, PinReads, board, pin_index},
+    },
     riot::{Riot, reads::RiotLineReads},
 };
+use arrayvec::ArrayVec;
+use core::slice;
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct EmuLineStates {
-    pub a: MultiRead<13>,
-    pub db: MultiRead<8>,
-    pub inp1: MultiRead<7>,
-    pub inp2: MultiRead<7>,
-    pub rdiff: SingleRead,
-    pub ldiff: SingleRead,
-    pub col: SingleRead,
-    pub sel: SingleRead,
-    pub res: SingleRead,
-    pub rw: SingleRead,
-    pub rdy: SingleRead,
+board! {
+    drivers {
+        cpu: Cpu,
+        riot: Riot,
+        tia: Beam,
+        ext: ExtDrives,
+    }
+    reads {
+        cpu: CpuLineReads { db, rdy },
+        riot: RiotLineReads { a, db, pa, pb, cs1, cs2, rs, rw },
+    }
+    buses {
+        a[13] {
+            Cpu "A" #0 Output [0..13] drive cpu.a_out[0];
+            Riot "A" #0 Input [0..7] read riot.a[0];
+            Riot "RS" Input [9..10] read riot.rs;
+            Riot "CS1" Input [11..12] read riot.cs1;
+            Riot "CS2" Input [12..13] read riot.cs2;
+            External "A" #0 Bidirectional [0..13] drive ext.a[0];
+        }
+        db[8] {
+            Cpu "D" #0 Bidirectional [0..8] drive cpu.db_out[0] read cpu.db[0];
+            Riot "D" #0 Bidirectional [0..8] drive riot.db_out[0] read riot.db[0];
+            Cartridge "D" #0 Output [0..8] drive ext.db[0];
+        }
+        inp1[7] {
+            Riot "PA" #4 Bidirectional [0..4] drive riot.pa_out[4] read riot.pa[4];
+            External "P1_" #0 Bidirectional [0..7] drive ext.inp1[0];
+        }
+        inp2[7] {
+            Riot "PA" #0 Bidirectional [0..4] drive riot.pa_out[0] read riot.pa[0];
+            External "P2_" #0 Bidirectional [0..7] drive ext.inp2[0];
+        }
+    }
+    lines {
+        rdiff {
+            Riot "PB7" Bidirectional drive riot.pb_out[4] read riot.pb[4];
+            External "PB7" Bidirectional drive ext.rdiff;
+        }
+        ldiff {
+            Riot "PB6" Bidirectional drive riot.pb_out[3] read riot.pb[3];
+            External "PB6" Bidirectional drive ext.ldiff;
+        }
+        col {
+            Riot "PB3" Bidirectional drive riot.pb_out[2] read riot.pb[2];
+            External "PB3" Bidirectional drive ext.col;
+        }
+        sel {
+            Riot "PB1" Bidirectional drive riot.pb_out[1] read riot.pb[1];
+            External "PB1" Bidirectional drive ext.sel;
+        }
+        res {
+            Riot "PB0" Bidirectional drive riot.pb_out[0] read riot.pb[0];
+            External "PB0" Bidirectional drive ext.res;
+        }
+        rw {
+            Cpu "R/W" Output drive cpu.rw_out;
+            Riot "R/W" Input read riot.rw;
+        }
+        rdy {
+            Tia "RDY" Output drive tia.rdy_out;
+            Cpu "RDY" Input read cpu.rdy;
+        }
+    }
 }
 
-impl EmuLineStates {
-    pub fn new() -> Self {
-        Self {
-            a: [SingleRead::Unknown; _].into(),
-            db: [SingleRead::Unknown; _].into(),
-            inp1: [SingleRead::Unknown; _].into(),
-            inp2: [SingleRead::Unknown; _].into(),
-            rdiff: SingleRead::Unknown,
-            ldiff: SingleRead::Unknown,
-            col: SingleRead::Unknown,
-            sel: SingleRead::Unknown,
-            res: SingleRead::Unknown,
-            rw: SingleRead::Unknown,
-            rdy: SingleRead::Unknown,
-        }
+/// The number of nets, counting each bus as one.
+pub const NET_COUNT: usize = BUSES.len() + LINE_NAMES.len();
+
+/// The number of individual lines, counting each bit of a bus.
+pub const LINE_COUNT: usize = {
+    let mut count = LINE_NAMES.len();
+    let mut i = 0;
+    while i < BUSES.len() {
+        count += BUSES[i].1;
+        i += 1;
     }
+    count
+};
+
+/// The index of a net, with buses first in the order of [`BUSES`], then [`LINE_NAMES`].
+#[must_use]
+pub fn net_index(name: &str) -> Option<usize> {
+    BUSES
+        .iter()
+        .map(|&(bus_name, _)| bus_name)
+        .chain(LINE_NAMES)
+        .position(|n| n == name)
+}
 
-    pub fn update(
-        &mut self,
-        ext_drives: &ExtDrives,
-        cpu: &Cpu,
-        riot: &Riot,
-    ) -> Result<(), LineError> {
-        let mut inp1: MultiRead<_> = [SingleRead::Unknown; _].into();
-        for (bit, read) in inp1.iter_mut().enumerate() {
-            let ident = LineIdent::BusLine {
-                bus_name: "inp1",
-                bit,
-            };
-
-            *read = if bit < 4 {
-                let drives = [ext_drives.inp1[bit], riot.pa_out[bit + 4]].into_iter();
-                DriveState::contend_ok(drives, ident)?.read_ok(ident)?
-            } else {
-                ext_drives.inp1[bit].read_ok(ident)?
+/// The index of a line, with every bus bit first in the order of [`BUSES`], then [`LINE_NAMES`].
+#[must_use]
+pub fn line_index(ident: LineIdent) -> Option<usize> {
+    match ident {
+        LineIdent::BusLine { bus_name, bit } => {
+            let mut offset = 0;
+            for (name, width) in BUSES {
+                if name == bus_name {
+                    return (bit < width).then_some(offset + bit);
+                }
+                offset += width;
             }
+            None
         }
+        LineIdent::UniqueLine { name } => LINE_NAMES
+            .iter()
+            .position(|&n| n == name)
+            .map(|i| LINE_COUNT - LINE_NAMES.len() + i),
+    }
+}
 
-        let mut inp2: MultiRead<_> = [SingleRead::Unknown; _].into();
-        for (bit, read) in inp2.iter_mut().enumerate() {
-            let ident = LineIdent::BusLine {
-                bus_name: "inp2",
-                bit,
-            };
-
-            *read = if bit < 4 {
-                let drives = [ext_drives.inp2[bit], riot.pa_out[bit]].into_iter();
-                DriveState::contend_ok(drives, ident)?.read_ok(ident)?
-            } else {
-                ext_drives.inp2[bit].read_ok(ident)?
-            };
+#[must_use]
+pub fn line_ident(mut index: usize) -> Option<LineIdent> {
+    for (bus_name, width) in BUSES {
+        if index < width {
+            return Some(LineIdent::BusLine {
+                bus_name,
+                bit: index,
+            });
         }
+        index -= width;
+    }
 
-        macro_rules! create_lines {
-            ($(($name:ident, $drives:expr)),+ $(,)?) => {$(
-                let ident = LineIdent::UniqueLine {
-                    name: stringify!($name),
-                };
-                let $name = DriveState::contend_ok($drives.into_iter(), ident)?.read_ok(ident)?;
-            )+};
-        }
+    LINE_NAMES
+        .get(index)
+        .map(|&name| LineIdent::UniqueLine { name })
+}
 
-        macro_rules! create_buses {
-            ($(($name:ident, $drives:expr)),+ $(,)?) => {$(
-                let name_str = stringify!($name);
-                let $name = BusDriveState::contend_ok(&$drives, name_str)?.read_ok(name_str)?;
-            )+};
-        }
+/// Contends the drivers of a line and reads it, naming the drivers in any error. Lines which read
+/// cleanly without bus-hold are cached, which is returned along with the read.
+fn resolve_line(
+    contention: &mut Contention,
+    bus_hold: &mut BusHoldState,
+    cache: &mut DriveCache,
+    ident: LineIdent,
+    drivers: &ArrayVec<(LineDriver, DriveState), MAX_LINE_DRIVERS>,
+    (cycle, edge): (u64, ClockEdge),
+) -> Result<(SingleRead, bool), LineError> {
+    match DriveState::contend(drivers.iter().map(|&(_, drive)| drive)) {
+        Some(drive) => bus_hold.resolve(ident, drive).map(|read| {
+            let cached = bus_hold.get(ident.net_name()).is_none();
+            if cached {
+                cache.insert(ident, drivers, read);
+            }
+            (read, cached)
+        }),
+        None => contention
+            .short(ident, cycle)
+            .and_then(|drive| bus_hold.resolve(ident, drive))
+            .map(|read| (read, false)),
+    }
+    .map_err(|err| {
+        err.with_context(LineContext {
+            drivers: drivers
+                .iter()
+                .map(|&(driver, drive)| DriverState { driver, drive })
+                .collect(),
+            edge: Some(edge),
+            cycle: Some(cycle),
+        })
+    })
+}
 
-        create_buses!(
-            (a, [&ext_drives.a, &cpu.a_out]),
-            (db, [&ext_drives.db, &cpu.db_out, &riot.db_out])
-        );
-        create_lines!(
-            (rdiff, [ext_drives.rdiff, riot.pb_out[4]]),
-            (ldiff, [ext_drives.ldiff, riot.pb_out[3]]),
-            (col, [ext_drives.col, riot.pb_out[2]]),
-            (sel, [ext_drives.sel, riot.pb_out[1]]),
-            (res, [ext_drives.sel, riot.pb_out[0]])
-        );
+/// The number of address lines the TIA decodes its registers from.
+pub const TIA_ADDR_LINES: usize = 6;
 
-        let rw = cpu.rw_out.read_ok("rw".into())?;
-        let rdy = SingleRead::Unknown;
-
-        *self = Self {
-            a,
-            db,
-            inp1,
-            inp2,
-            rdiff,
-            ldiff,
-            col,
-            sel,
-            res,
-            rw,
-            rdy,
-        };
+impl EmuLineStates {
+    /// Whether the bus is carrying a write to the TIA, which is selected when A12 and A7 are low.
+    #[must_use]
+    pub fn tia_write(&self) -> BaseCondition {
+        let mut tia: MultiRead<13> = [SingleRead::Unknown; _].into();
+        tia[7] = SingleRead::Low;
+        tia[12] = SingleRead::Low;
 
-        Ok(())
+        self.a.is(&tia) & !self.rw.as_cond()
     }
 
+    #[must_use]
     pub fn riot_reads(&self) -> RiotLineReads {
-        let mut pa: MultiRead<_> = [SingleRead::Unknown; _].into();
-        pa[0..4].copy_from_slice(&self.inp2[0..4]);
-        pa[4..8].copy_from_slice(&self.inp1[0..4]);
-
-        let a_arr: [SingleRead; _] = self.a[0..7].try_into().expect("same-sized slices");
-
-        RiotLineReads {
-            a: a_arr.into(),
-            db: self.db.clone(),
-            pa,
-            pb: [self.res, self.sel, self.col, self.ldiff, self.rdiff].into(),
-            cs1: self.a[11],
-            cs2: self.a[12],
-            rs: self.a[9],
-            rw: self.rw,
-        }
+        self.reads().riot
     }
 
+    #[must_use]
     pub fn cpu_reads(&self) -> CpuLineReads {
-        CpuLineReads {
-            db: self.db.clone(),
-            rdy: self.rdy,
-        }
+        self.reads().cpu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{common::line::multi::BusDriveState, full::Emulator};
+
+    #[test]
+    fn routes_reads_by_wiring() {
+        let mut lines = EmuLineStates::new();
+        lines.inp1 = MultiRead::from_value(0b101);
+        lines.inp2 = MultiRead::from_value(0b1010);
+        lines.a = MultiRead::from_value(1 << 11 | 0x5a);
+        lines.rdiff = SingleRead::Low;
+
+        let riot = lines.riot_reads();
+        assert_eq!(riot.pa, MultiRead::from_value(0x5a));
+        assert_eq!(riot.a, MultiRead::from_value(0x5a));
+        assert_eq!(riot.pb[4], SingleRead::Low);
+        assert_eq!(riot.pb[0], SingleRead::Unknown);
+        assert_eq!((riot.cs1, riot.cs2), (SingleRead::High, SingleRead::Low));
+    }
+
+    #[test]
+    fn names_cartridge_in_data_bus_shorts() {
+        let mut emu = Emulator::new();
+        emu.cpu.db_out = BusDriveState::from_value(0);
+        let mut ext = ExtDrives::new();
+        ext.db = BusDriveState::from_value(1);
+
+        let drivers = BoardDrivers {
+            cpu: &emu.cpu,
+            riot: &emu.riot,
+            tia: &emu.beam,
+            ext: &ext,
+        };
+        let Err(LineError::ShortCircuit { context, .. }) =
+            emu.line_states
+                .update(drivers, &mut emu.contention, (0, ClockEdge::Rising))
+        else {
+            panic!("expected a short circuit");
+        };
+        assert!(
+            context
+                .drivers
+                .iter()
+                .any(|state| state.driver == LineDriver::Cartridge)
+        );
     }
 }
@@ -1,12 +1,55 @@
+pub mod beam;
+pub mod bits;
+pub mod bus_hold;
+pub mod concretize;
+pub mod config;
+pub mod console;
+pub mod contention;
+#[cfg(any(test, feature = "untested-analyses"))]
+pub mod determinism;
+pub mod dirty;
 pub mod ext_drives;
+#[cfg(any(test, feature = "untested-analyses"))]
+pub mod fixpoint;
+pub mod fork;
+#[cfg(any(test, feature = "untested-analyses"))]
+pub mod input_dependence;
+pub mod joystick;
 pub mod line_reads;
+pub mod netlist;
+pub mod power_on;
+#[cfg(any(test, feature = "untested-analyses"))]
+pub mod power_on_check;
+#[cfg(any(test, feature = "untested-analyses"))]
+pub mod provenance;
+pub mod rewind;
+pub mod save_state;
+pub mod settle;
+pub mod source;
+pub mod symbolic;
+pub mod tick;
+pub mod wiring;
 
+#[cfg(any(test, feature = "untested-analyses"))]
+use crate::full::source::DriveSource;
 use crate::{
-    common::line::error::LineError,
+    common::{
+        combine::Combine,
+        line::error::{ClockEdge, LineError},
+    },
     cpu::Cpu,
-    full::{ext_drives::ExtDrives, line_reads::EmuLineStates},
+    full::{
+        beam::Beam,
+        bus_hold::BusHold,
+        contention::{Contention, ContentionLog, ContentionPolicy},
+        ext_drives::ExtDrives,
+        line_reads::{BoardDrivers, EmuLineStates},
+        settle::{DEFAULT_SETTLE_LIMIT, NetSet},
+        tick::{TickError, TickStage, TickUndo},
+    },
     riot::Riot,
 };
+use core::num::NonZeroU32;
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Emulator {
@@ -14,6 +57,10 @@ pub struct Emulator {
     riot: Riot,
     phi0: bool,
     line_states: EmuLineStates,
+    cycles: u64,
+    beam: Beam,
+    contention: Contention,
+    settle_limit: NonZeroU32,
 }
 
 impl Default for Emulator {
@@ -30,26 +77,214 @@ pub fn new() -> Self {
             riot: Riot::new(),
             phi0: false,
             line_states: EmuLineStates::new(),
+            cycles: 0,
+            beam: Beam::new(),
+            contention: Contention::new(),
+            settle_limit: DEFAULT_SETTLE_LIMIT,
         }
     }
 
-    fn update(&mut self, ext: &ExtDrives) -> Result<(), LineError> {
-        self.line_states.update(ext, &self.cpu, &self.riot)
+    #[must_use]
+    pub fn bus_hold(&self, net: &str) -> Option<BusHold> {
+        self.line_states.hold.get(net)
+    }
+
+    /// Sets whether a net keeps its last driven value while floating, returning `false` if there
+    /// is no such net.
+    pub fn set_bus_hold(&mut self, net: &str, hold: Option<BusHold>) -> bool {
+        self.line_states.hold.set(net, hold)
+    }
+
+    #[must_use]
+    pub const fn contention_policy(&self) -> ContentionPolicy {
+        self.contention.policy
+    }
+
+    pub const fn set_contention_policy(&mut self, policy: ContentionPolicy) {
+        self.contention.policy = policy;
+    }
+
+    /// The short circuits resolved under [`ContentionPolicy::ResolveAndLog`].
+    #[must_use]
+    pub const fn contention_log(&self) -> &ContentionLog {
+        &self.contention.log
+    }
+
+    pub fn clear_contention_log(&mut self) {
+        self.contention.log.clear();
+    }
+
+    /// The number of update passes allowed for the lines to settle before each edge.
+    #[must_use]
+    pub const fn settle_limit(&self) -> NonZeroU32 {
+        self.settle_limit
+    }
+
+    pub const fn set_settle_limit(&mut self, limit: NonZeroU32) {
+        self.settle_limit = limit;
+    }
+
+    #[must_use]
+    pub const fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    #[must_use]
+    pub const fn frame(&self) -> u64 {
+        self.beam.frame()
+    }
+
+    #[must_use]
+    pub const fn scanline(&self) -> u16 {
+        self.beam.scanline()
+    }
+
+    #[must_use]
+    pub const fn color_clock(&self) -> u16 {
+        self.beam.color_clock()
+    }
+
+    /// Updates the lines from the current drives, returning the nets which changed.
+    fn update(&mut self, ext: &ExtDrives, edge: ClockEdge) -> Result<NetSet, LineError> {
+        let drivers = BoardDrivers {
+            cpu: &self.cpu,
+            riot: &self.riot,
+            tia: &self.beam,
+            ext,
+        };
+
+        self.line_states
+            .update(drivers, &mut self.contention, (self.cycles, edge))
+    }
+
+    /// Recomputes the outputs which follow the lines without waiting for a clock edge, returning
+    /// whether any changed. Only the RIOT's data bus drive does, while phi2 is high.
+    fn update_outputs(&mut self, stage: TickStage) -> bool {
+        if stage.edge() != ClockEdge::Falling {
+            return false;
+        }
+
+        let before = self.riot.db_out.clone();
+        self.riot.update_db_out(self.line_states.riot_reads());
+        self.riot.db_out != before
+    }
+
+    /// Runs one clock cycle. If any stage fails, the emulator is left as it was before the call.
+    ///
+    /// The CPU's clock edge handlers are not implemented yet, so this panics once the lines settle
+    /// for the first edge. Only ticks which fail before that are tested.
+    pub fn tick(&mut self, ext: &ExtDrives) -> Result<(), TickError> {
+        let undo = TickUndo::new(self);
+        self.tick_in_place(ext).inspect_err(|_| undo.restore(self))
     }
 
-    pub fn tick(&mut self, ext: &ExtDrives) -> Result<(), LineError> {
-        self.update(ext)?;
+    fn tick_in_place(&mut self, ext: &ExtDrives) -> Result<(), TickError> {
+        self.settle(ext, TickStage::CpuRisingEdge)?;
         self.cpu.handle_rising_edge(self.line_states.cpu_reads());
 
-        self.update(ext)?;
+        self.settle(ext, TickStage::RiotRisingEdge)?;
         self.riot.handle_rising_edge(self.line_states.riot_reads());
+        self.line_states.hold.advance_half_cycle();
 
-        self.update(ext)?;
+        self.settle(ext, TickStage::CpuFallingEdge)?;
         self.cpu.handle_falling_edge(self.line_states.cpu_reads());
 
-        self.update(ext)?;
+        self.settle(ext, TickStage::RiotFallingEdge)?;
         self.riot.handle_falling_edge();
+        self.line_states.hold.advance_half_cycle();
+
+        self.cycles += 1;
+        self.beam.observe_bus(&self.line_states);
+        self.beam.advance_cycle();
+
+        Ok(())
+    }
+
+    /// Ticks until `predicate` holds, taking the drives for each tick from `source`.
+    #[cfg(any(test, feature = "untested-analyses"))]
+    pub fn run_until(
+        &mut self,
+        source: &mut impl DriveSource,
+        mut predicate: impl FnMut(&Self) -> bool,
+    ) -> Result<(), TickError> {
+        while !predicate(self) {
+            let ext = source.drives(self);
+            self.tick(&ext)?;
+        }
 
         Ok(())
     }
+
+    /// Ticks until the beam starts the next frame.
+    #[cfg(any(test, feature = "untested-analyses"))]
+    pub fn run_frame(&mut self, source: &mut impl DriveSource) -> Result<(), TickError> {
+        let frame = self.frame();
+        self.run_until(source, |emu| emu.frame() != frame)
+    }
+}
+
+/// Both states must be at the same point within a frame, which is checked in debug builds. The
+/// frame and cycle counts, contention policy and log, and settle limit are taken from `self`, so
+/// states from different frames can still be joined, as the fixpoint runner does.
+impl Combine for Emulator {
+    fn combine_with(&self, other: &Self) -> Self {
+        debug_assert_eq!(
+            (self.phi0, self.scanline(), self.color_clock()),
+            (other.phi0, other.scanline(), other.color_clock()),
+            "cannot combine states at different points within a frame"
+        );
+
+        Self {
+            cpu: self.cpu.combine_with(&other.cpu),
+            riot: self.riot.combine_with(&other.riot),
+            phi0: self.phi0,
+            line_states: self.line_states.combine_with(&other.line_states),
+            cycles: self.cycles,
+            beam: self.beam,
+            contention: self.contention.clone(),
+            settle_limit: self.settle_limit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::read::{multi::MultiRead, single::SingleRead};
+
+    #[test]
+    fn combines_differing_bits() {
+        let mut a = Emulator::new();
+        a.riot.ram_mut()[0] = MultiRead::from_value(0b0101);
+        a.beam.advance_cycle();
+        a.cycles = 1;
+
+        let mut b = a.clone();
+        b.riot.ram_mut()[0] = MultiRead::from_value(0b0110);
+        b.cycles = 9;
+
+        let joined = a.combine_with(&b);
+        assert_eq!(
+            joined.riot.ram()[0][..4],
+            [
+                SingleRead::Unknown,
+                SingleRead::Unknown,
+                SingleRead::High,
+                SingleRead::Low
+            ]
+        );
+        assert_eq!(joined.cycles(), 1);
+        assert!(joined.covers(&a) && joined.covers(&b));
+        assert!(!a.covers(&b));
+    }
+
+    #[test]
+    #[should_panic(expected = "different points within a frame")]
+    fn rejects_mismatched_timing() {
+        let a = Emulator::new();
+        let mut b = Emulator::new();
+        b.beam.advance_cycle();
+
+        let _ = a.combine_with(&b);
+    }
 }
@@ -0,0 +1,164 @@
+use crate::{
+    common::line::{error::LineDriver, ident::LineIdent},
+    full::line_reads::{NETS, net_index},
+};
+use core::fmt;
+use strum_macros::Display;
+
+/// Which way signals pass through a pin, as seen from its component.
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum PinDirection {
+    #[strum(to_string = "input")]
+    Input,
+
+    #[strum(to_string = "output")]
+    Output,
+
+    #[strum(to_string = "bidirectional")]
+    Bidirectional,
+}
+
+impl PinDirection {
+    #[must_use]
+    pub const fn can_drive(self) -> bool {
+        !matches!(self, Self::Input)
+    }
+
+    #[must_use]
+    pub const fn can_read(self) -> bool {
+        !matches!(self, Self::Output)
+    }
+}
+
+/// A pin, or a group of numbered pins, connecting a component to consecutive bits of a net.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Pin {
+    pub component: LineDriver,
+    pub name: &'static str,
+    pub direction: PinDirection,
+
+    /// The first bit of the net the pins connect to.
+    pub net_bit: usize,
+
+    /// The number of the first pin in a group, or `None` for a single pin.
+    pub first_index: Option<usize>,
+
+    pub width: usize,
+}
+
+impl Pin {
+    #[must_use]
+    pub const fn contains(&self, bit: usize) -> bool {
+        bit >= self.net_bit && bit < self.net_bit + self.width
+    }
+
+    /// The pin connected to a bit of the net, if this pin covers it.
+    #[must_use]
+    pub const fn at(&self, bit: usize) -> Option<PinAt> {
+        if self.contains(bit) {
+            Some(PinAt { pin: *self, bit })
+        } else {
+            None
+        }
+    }
+}
+
+/// A single pin out of a [`Pin`] group, such as `RIOT PA4`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct PinAt {
+    pub pin: Pin,
+    pub bit: usize,
+}
+
+impl fmt::Display for PinAt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.pin.component, self.pin.name)?;
+
+        if let Some(first) = self.pin.first_index {
+            write!(f, "{}", first + self.bit - self.pin.net_bit)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A net on the board, which is either a single line or a bus.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Net {
+    pub name: &'static str,
+    pub width: usize,
+    pub pins: &'static [Pin],
+}
+
+impl Net {
+    #[must_use]
+    pub const fn is_bus(&self) -> bool {
+        self.width > 1
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = LineIdent> + use<> {
+        let (name, is_bus) = (self.name, self.is_bus());
+
+        (0..self.width).map(move |bit| {
+            if is_bus {
+                LineIdent::BusLine {
+                    bus_name: name,
+                    bit,
+                }
+            } else {
+                LineIdent::UniqueLine { name }
+            }
+        })
+    }
+
+    /// The pins connected to a bit of the net.
+    pub fn pins_at(&self, bit: usize) -> impl Iterator<Item = PinAt> + use<> {
+        self.pins.iter().filter_map(move |pin| pin.at(bit))
+    }
+
+    pub fn drivers_at(&self, bit: usize) -> impl Iterator<Item = PinAt> + use<> {
+        self.pins_at(bit)
+            .filter(|pin| pin.pin.direction.can_drive())
+    }
+
+    pub fn readers_at(&self, bit: usize) -> impl Iterator<Item = PinAt> + use<> {
+        self.pins_at(bit).filter(|pin| pin.pin.direction.can_read())
+    }
+}
+
+/// Looks up a net by name.
+#[must_use]
+pub fn net(name: &str) -> Option<&'static Net> {
+    NETS.get(net_index(name)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::full::line_reads::{BUSES, LINE_NAMES};
+    use arrayvec::{ArrayString, ArrayVec};
+    use core::fmt::Write;
+
+    #[test]
+    fn matches_line_reads() {
+        let widths = BUSES.into_iter().chain(LINE_NAMES.map(|name| (name, 1)));
+
+        for (net, (name, width)) in NETS.iter().zip(widths) {
+            assert_eq!((net.name, net.width), (name, width));
+            assert!(net.pins.iter().all(|pin| pin.net_bit + pin.width <= width));
+        }
+    }
+
+    #[test]
+    fn names_pins() {
+        let mut names = ArrayString::<64>::new();
+        for pin in net("inp1").unwrap().drivers_at(2) {
+            write!(names, "{pin}; ").unwrap();
+        }
+        assert_eq!(names.as_str(), "RIOT PA6; external P1_2; ");
+
+        let readers: ArrayVec<_, 4> = net("a").unwrap().readers_at(11).collect();
+        assert_eq!(readers.len(), 2);
+        assert_eq!(readers[0].pin.name, "CS1");
+    }
+}
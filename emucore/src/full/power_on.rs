@@ -0,0 +1,272 @@
+use crate::{
+    common::{
+        codec::{CodecError, Decode, Encode, Reader, Writer},
+        read::single::SingleRead,
+        rng::{BitSource, SplitMix64},
+    },
+    full::{Emulator, bits::StateBit, concretize::StateScope},
+    riot::RAM_SIZE,
+};
+use strum_macros::Display;
+
+/// The size of a power-on image, which is enough to cover the largest component.
+pub const POWER_ON_IMAGE_LEN: usize = RAM_SIZE;
+
+/// How the undefined parts of the console's state are filled in at power-on.
+///
+/// Only the bits the hardware leaves undefined are affected, so registers with a defined reset
+/// value keep it under every policy.
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum PowerOnPolicy {
+    /// Every register and RAM cell the hardware leaves undefined starts Unknown.
+    #[strum(to_string = "unknown")]
+    Unknown,
+
+    /// Undefined bits start low, like most software emulators.
+    #[strum(to_string = "zeroed")]
+    Zero,
+
+    /// Undefined bits are drawn from a PRNG, like real hardware.
+    #[strum(to_string = "seeded {seed:#x}")]
+    Seeded { seed: u64 },
+
+    /// Undefined bits are taken from an image holding the component's bits in [`StateBit::all`]
+    /// order, least significant bit first. For RAM this is one byte per address.
+    #[strum(to_string = "image")]
+    Image { bytes: [u8; POWER_ON_IMAGE_LEN] },
+}
+
+impl PowerOnPolicy {
+    /// This policy with its seed mixed with `component`'s, so components seeded alike still get
+    /// independent bits.
+    #[must_use]
+    const fn for_component(self, component: PowerOnComponent) -> Self {
+        match self {
+            Self::Seeded { seed } => Self::Seeded {
+                seed: SplitMix64::new(seed ^ component.salt()).next_u64(),
+            },
+            _ => self,
+        }
+    }
+
+    /// Fills the Unknown bits within `scope` according to this policy.
+    pub(crate) fn fill_unknown(&self, emu: &mut Emulator, scope: StateScope) {
+        let mut rng = match *self {
+            Self::Seeded { seed } => Some(BitSource::new(seed)),
+            _ => None,
+        };
+
+        for (i, bit) in StateBit::all()
+            .filter(|&bit| scope.contains(bit))
+            .enumerate()
+        {
+            let Some(read) = emu.bit_mut(bit) else {
+                continue;
+            };
+
+            if *read != SingleRead::Unknown {
+                continue;
+            }
+
+            let value = match self {
+                Self::Unknown => None,
+                Self::Zero => Some(false),
+                Self::Seeded { .. } => rng.as_mut().map(BitSource::next_bit),
+                Self::Image { bytes } => bytes.get(i / 8).map(|byte| byte >> (i % 8) & 1 == 1),
+            };
+
+            if let Some(value) = value {
+                *read = SingleRead::from(value);
+            }
+        }
+    }
+}
+
+impl Encode for PowerOnPolicy {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        match self {
+            Self::Unknown => w.write_u8(0),
+            Self::Zero => w.write_u8(1),
+            Self::Seeded { seed } => {
+                w.write_u8(2)?;
+                w.write_u64(*seed)
+            }
+            Self::Image { bytes } => {
+                w.write_u8(3)?;
+                w.write_bytes(bytes)
+            }
+        }
+    }
+}
+
+impl Decode for PowerOnPolicy {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        match r.read_u8()? {
+            0 => Ok(Self::Unknown),
+            1 => Ok(Self::Zero),
+            2 => Ok(Self::Seeded {
+                seed: r.read_u64()?,
+            }),
+            3 => Ok(Self::Image {
+                bytes: r.read_bytes()?,
+            }),
+            _ => Err(CodecError::InvalidValue),
+        }
+    }
+}
+
+/// A component with its own power-on policy.
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum PowerOnComponent {
+    #[strum(to_string = "CPU register")]
+    Cpu,
+
+    #[strum(to_string = "RIOT register")]
+    Riot,
+
+    #[strum(to_string = "RIOT RAM")]
+    Ram,
+}
+
+impl PowerOnComponent {
+    const fn salt(self) -> u64 {
+        (self as u64 + 1).wrapping_mul(0xd6e8_feb8_6659_fd93)
+    }
+}
+
+/// The power-on policy of each component.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct PowerOnConfig {
+    pub cpu: PowerOnPolicy,
+    pub riot: PowerOnPolicy,
+    pub ram: PowerOnPolicy,
+}
+
+impl Default for PowerOnConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PowerOnConfig {
+    /// Leaves every undefined bit Unknown.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::uniform(PowerOnPolicy::Unknown)
+    }
+
+    /// Uses the same policy for every component.
+    #[must_use]
+    pub const fn uniform(policy: PowerOnPolicy) -> Self {
+        Self {
+            cpu: policy,
+            riot: policy,
+            ram: policy,
+        }
+    }
+
+    /// The first component whose policy differs from `other`'s.
+    #[must_use]
+    pub fn mismatch(&self, other: &Self) -> Option<PowerOnComponent> {
+        [
+            (PowerOnComponent::Cpu, self.cpu == other.cpu),
+            (PowerOnComponent::Riot, self.riot == other.riot),
+            (PowerOnComponent::Ram, self.ram == other.ram),
+        ]
+        .into_iter()
+        .find_map(|(component, same)| (!same).then_some(component))
+    }
+
+    pub(crate) fn apply(&self, emu: &mut Emulator) {
+        let scope = |cpu, riot, ram| StateScope {
+            cpu,
+            riot,
+            ram,
+            lines: false,
+        };
+
+        self.cpu
+            .for_component(PowerOnComponent::Cpu)
+            .fill_unknown(emu, scope(true, false, false));
+        self.riot
+            .for_component(PowerOnComponent::Riot)
+            .fill_unknown(emu, scope(false, true, false));
+        self.ram
+            .for_component(PowerOnComponent::Ram)
+            .fill_unknown(emu, StateScope::RAM);
+    }
+}
+
+impl Encode for PowerOnConfig {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        self.cpu.encode(w)?;
+        self.riot.encode(w)?;
+        self.ram.encode(w)
+    }
+}
+
+impl Decode for PowerOnConfig {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        Ok(Self {
+            cpu: Decode::decode(r)?,
+            riot: Decode::decode(r)?,
+            ram: Decode::decode(r)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{common::read::multi::MultiRead, cpu::regs::CpuReg, riot::regs::RiotReg};
+
+    #[test]
+    fn fills_only_undefined_bits() {
+        let mut bytes = [0; POWER_ON_IMAGE_LEN];
+        bytes[3] = 0xa5;
+
+        let mut emu = Emulator::new();
+        PowerOnConfig {
+            cpu: PowerOnPolicy::Unknown,
+            riot: PowerOnPolicy::Seeded { seed: 3 },
+            ram: PowerOnPolicy::Image { bytes },
+        }
+        .apply(&mut emu);
+
+        assert_eq!(emu.riot.ram()[3], MultiRead::from_value(0xa5));
+        assert_eq!(
+            emu.riot.regs().bits(RiotReg::Ddra),
+            Emulator::new().riot.regs().bits(RiotReg::Ddra)
+        );
+        assert!(
+            StateBit::all()
+                .filter(|bit| matches!(bit, StateBit::Riot { .. } | StateBit::Ram { .. }))
+                .all(|bit| emu.bit(bit) != Some(SingleRead::Unknown))
+        );
+        assert_eq!(
+            emu.bit(StateBit::Cpu {
+                reg: CpuReg::A,
+                bit: 0
+            }),
+            Some(SingleRead::Unknown)
+        );
+    }
+
+    #[test]
+    fn seeds_components_independently() {
+        let mut emu = Emulator::new();
+        PowerOnConfig::uniform(PowerOnPolicy::Seeded { seed: 9 }).apply(&mut emu);
+
+        let fresh = Emulator::new();
+        let filled = |ram: bool| {
+            StateBit::all()
+                .filter(move |bit| matches!(bit, StateBit::Ram { .. }) == ram)
+                .filter(|&bit| fresh.bit(bit) == Some(SingleRead::Unknown))
+                .filter(|bit| !matches!(bit, StateBit::Line { .. }))
+                .take(64)
+                .map(|bit| emu.bit(bit))
+        };
+
+        assert!(filled(false).ne(filled(true)));
+    }
+}
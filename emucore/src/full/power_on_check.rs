@@ -0,0 +1,275 @@
+use crate::{
+    common::{
+        combine::Combine,
+        cond::{base::BaseCondition, check::CheckIs},
+        line::ident::LineIdent,
+        read::single::SingleRead,
+    },
+    cpu::regs::CpuReg,
+    full::{
+        Emulator,
+        bits::StateBit,
+        line_reads::TIA_ADDR_LINES,
+        provenance::{LabelSet, Provenance},
+        source::DriveSource,
+        tick::TickError,
+    },
+};
+
+/// The flag each conditional branch tests, indexed by bits 7 and 6 of its opcode.
+const BRANCH_FLAGS: [CpuReg; 4] = [CpuReg::N, CpuReg::V, CpuReg::C, CpuReg::Z];
+
+/// Where an Unknown value ended up.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum UnknownSink {
+    /// A conditional branch could have gone either way, as its flag or the condition it selects
+    /// was Unknown.
+    Branch,
+    /// A TIA write may have taken place, or taken place with an Unknown address or Unknown data.
+    TiaWrite,
+    /// The address bus may have selected the given bankswitch hotspot.
+    Hotspot { addr: u16 },
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct UnknownUse {
+    pub sink: UnknownSink,
+    pub cycle: u64,
+    /// The last fully known program counter.
+    pub pc: Option<u16>,
+    /// Where the Unknown inputs of the sink came from, as traced by [`Provenance`].
+    pub origin: LabelSet,
+}
+
+/// Runs from the fully Unknown power-on state and reports every cycle where an Unknown value
+/// reaches a branch condition, a TIA write or a bankswitch hotspot.
+///
+/// The cartridge is whatever drives the data bus through the [`DriveSource`], and `hotspots` are
+/// its bankswitch addresses.
+pub struct PowerOnCheck<'a> {
+    hotspots: &'a [u16],
+    pc: Option<u16>,
+}
+
+impl<'a> PowerOnCheck<'a> {
+    #[must_use]
+    pub const fn new(hotspots: &'a [u16]) -> Self {
+        Self { hotspots, pc: None }
+    }
+
+    /// Runs a fresh emulator for `frames` frames, returning it once done.
+    pub fn run(
+        &mut self,
+        source: &mut impl DriveSource,
+        frames: u64,
+        mut on_use: impl FnMut(&UnknownUse),
+    ) -> Result<Emulator, TickError> {
+        let mut emu = Emulator::new();
+        let mut provenance = Provenance::power_on(&emu);
+
+        while emu.frame() < frames {
+            let ext = source.drives(&emu);
+            emu.tick_traced(&ext, &mut provenance)?;
+            self.observe(&emu, &provenance, &mut on_use);
+        }
+
+        Ok(emu)
+    }
+
+    fn observe(
+        &mut self,
+        emu: &Emulator,
+        provenance: &Provenance,
+        on_use: &mut impl FnMut(&UnknownUse),
+    ) {
+        self.pc = emu.known_pc().or(self.pc);
+
+        let lines = &emu.line_states;
+        let net_labels = |bus_name, bits: usize| {
+            (0..bits)
+                .filter_map(|bit| {
+                    provenance.read(
+                        emu,
+                        StateBit::Line {
+                            ident: LineIdent::BusLine { bus_name, bit },
+                        },
+                    )
+                })
+                .fold(LabelSet::new(), |acc, read| acc.combine_with(&read.labels))
+        };
+        let mut report = |sink, origin| {
+            on_use(&UnknownUse {
+                sink,
+                cycle: emu.cycles,
+                pc: self.pc,
+                origin,
+            });
+        };
+
+        if let Some(flags) = undecided_branch(emu, provenance) {
+            report(
+                UnknownSink::Branch,
+                flags.combine_with(&net_labels("db", 8)),
+            );
+        }
+
+        let tia_write = lines.tia_write();
+        if tia_write != BaseCondition::No
+            && (tia_write == BaseCondition::Unknown
+                || lines.a[..TIA_ADDR_LINES].contains(&SingleRead::Unknown)
+                || lines.db.contains(&SingleRead::Unknown))
+        {
+            let origin = net_labels("a", 13)
+                .combine_with(&net_labels("db", 8))
+                .combine_with(
+                    &provenance
+                        .read(emu, StateBit::Line { ident: "rw".into() })
+                        .map_or(LabelSet::new(), |read| read.labels),
+                );
+            report(UnknownSink::TiaWrite, origin);
+        }
+
+        if lines.a.contains(&SingleRead::Unknown) {
+            for &addr in self.hotspots {
+                if lines.a.is(usize::from(addr & 0x1fff)) != BaseCondition::No {
+                    report(UnknownSink::Hotspot { addr }, net_labels("a", 13));
+                }
+            }
+        }
+    }
+}
+
+/// Checks whether a conditional branch is being decided either way, returning the labels of the
+/// Unknown flags it could test if so.
+///
+/// A branch is decided on its second cycle, when the CPU is on instruction cycle 1 and the data
+/// bus still holds the opcode. Every opcode the data bus could hold is considered, so an Unknown
+/// opcode bit which selects a different flag or condition counts as well.
+fn undecided_branch(emu: &Emulator, provenance: &Provenance) -> Option<LabelSet> {
+    let regs = emu.cpu.regs();
+    if regs.instr_cycle.is(1) == BaseCondition::No {
+        return None;
+    }
+
+    let (mut taken, mut skipped) = (false, false);
+    let mut labels = LabelSet::new();
+
+    for opcode in emu.line_states.db.iter_possible_reads() {
+        if opcode & 0x1f != 0x10 {
+            continue;
+        }
+
+        let flag = BRANCH_FLAGS[usize::from(opcode >> 6)];
+        let expected = opcode >> 5 & 1 == 1;
+        match regs.bits(flag)[0].as_bool() {
+            Some(value) if value == expected => taken = true,
+            Some(_) => skipped = true,
+            None => {
+                (taken, skipped) = (true, true);
+                if let Some(read) = provenance.read(emu, StateBit::Cpu { reg: flag, bit: 0 }) {
+                    labels = labels.combine_with(&read.labels);
+                }
+            }
+        }
+    }
+
+    (taken && skipped).then_some(labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{common::read::multi::MultiRead, full::provenance::Label};
+    use arrayvec::ArrayVec;
+
+    fn observe(check: &mut PowerOnCheck<'_>, emu: &Emulator) -> ArrayVec<UnknownUse, 4> {
+        let provenance = Provenance::power_on(emu);
+        let mut uses = ArrayVec::new();
+        check.observe(emu, &provenance, &mut |u| uses.push(*u));
+        uses
+    }
+
+    /// An emulator on the decision cycle of BEQ, with every other sink known.
+    fn deciding_beq() -> Emulator {
+        let mut emu = Emulator::new();
+        emu.line_states.a = MultiRead::from_value(0x1000);
+        emu.line_states.rw = SingleRead::High;
+        emu.line_states.db = MultiRead::from_value(0xf0);
+        emu.cpu.regs_mut().instr_cycle = MultiRead::from_value(1);
+        emu
+    }
+
+    #[test]
+    fn reports_branches_on_unknown_flags() {
+        let mut check = PowerOnCheck::new(&[]);
+        let mut emu = deciding_beq();
+
+        let uses = observe(&mut check, &emu);
+        assert_eq!(uses.len(), 1);
+        assert_eq!(uses[0].sink, UnknownSink::Branch);
+        assert!(
+            uses[0]
+                .origin
+                .iter()
+                .eq([Label::PowerOnCpu { reg: CpuReg::Z }])
+        );
+
+        emu.cpu.regs_mut().z = SingleRead::High;
+        assert!(observe(&mut check, &emu).is_empty());
+
+        emu.cpu.regs_mut().instr_cycle = MultiRead::from_value(2);
+        emu.cpu.regs_mut().z = SingleRead::Unknown;
+        assert!(observe(&mut check, &emu).is_empty());
+    }
+
+    #[test]
+    fn reports_branches_on_unknown_conditions() {
+        let mut check = PowerOnCheck::new(&[]);
+        let mut emu = deciding_beq();
+        emu.cpu.regs_mut().z = SingleRead::High;
+
+        // BEQ or BNE on a known flag still goes either way.
+        emu.line_states.db[5] = SingleRead::Unknown;
+        let uses = observe(&mut check, &emu);
+        assert_eq!(uses.len(), 1);
+        assert_eq!(uses[0].sink, UnknownSink::Branch);
+    }
+
+    #[test]
+    fn reports_tia_write_with_unknown_data_or_address() {
+        let mut check = PowerOnCheck::new(&[]);
+        let mut emu = Emulator::new();
+
+        emu.line_states.a = MultiRead::from_value(0x0009);
+        emu.line_states.rw = SingleRead::Low;
+        emu.line_states.db = MultiRead::from_value(0x42);
+        assert!(observe(&mut check, &emu).is_empty());
+
+        emu.line_states.a[2] = SingleRead::Unknown;
+        let uses = observe(&mut check, &emu);
+        assert_eq!(uses.len(), 1);
+        assert_eq!(uses[0].sink, UnknownSink::TiaWrite);
+
+        emu.line_states.a = MultiRead::from_value(0x0009);
+        emu.line_states.db[0] = SingleRead::Unknown;
+        let uses = observe(&mut check, &emu);
+        assert_eq!(uses.len(), 1);
+        assert_eq!(uses[0].sink, UnknownSink::TiaWrite);
+    }
+
+    #[test]
+    fn reports_uncertain_hotspot_access() {
+        let mut check = PowerOnCheck::new(&[0x1ff8, 0x1ff9]);
+        let mut emu = Emulator::new();
+
+        emu.line_states.a = MultiRead::from_value(0x1ff8);
+        emu.line_states.rw = SingleRead::High;
+        emu.line_states.db = MultiRead::from_value(0);
+        assert!(observe(&mut check, &emu).is_empty());
+
+        emu.line_states.a[0] = SingleRead::Unknown;
+        let uses = observe(&mut check, &emu);
+        assert_eq!(uses.len(), 2);
+        assert_eq!(uses[1].sink, UnknownSink::Hotspot { addr: 0x1ff9 });
+    }
+}
@@ -0,0 +1,514 @@
+use crate::{
+    common::{
+        combine::Combine,
+        line::{
+            error::{LineDriver, MAX_LINE_DRIVERS},
+            ident::LineIdent,
+            single::DriveState,
+        },
+        read::single::SingleRead,
+        signal::LineSignal,
+    },
+    cpu::regs::CpuReg,
+    full::{
+        Emulator,
+        bits::StateBit,
+        ext_drives::ExtDrives,
+        line_reads::{BoardDrivers, LINE_COUNT, NET_COUNT, line_ident, line_index, net_index},
+        tick::TickError,
+    },
+    riot::{RAM_SIZE, regs::RiotReg},
+};
+use arrayvec::ArrayVec;
+use core::{array, fmt};
+
+const RIOT_BASE: usize = CpuReg::ALL.len();
+const RAM_BASE: usize = RIOT_BASE + RiotReg::ALL.len();
+const LINE_BASE: usize = RAM_BASE + RAM_SIZE;
+const ROM_INDEX: usize = LINE_BASE + LINE_COUNT;
+const LABEL_WORDS: usize = (ROM_INDEX + 1).div_ceil(64);
+
+/// Where an Unknown value could have come from.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Label {
+    PowerOnCpu { reg: CpuReg },
+    PowerOnRiot { reg: RiotReg },
+    PowerOnRam { addr: usize },
+    Input { ident: LineIdent },
+    Rom,
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PowerOnCpu { reg } => write!(f, "CPU {reg} at power-on"),
+            Self::PowerOnRiot { reg } => write!(f, "RIOT {reg} at power-on"),
+            Self::PowerOnRam { addr } => write!(f, "RIOT RAM ${:02X} at power-on", 0x80 + addr),
+            Self::Input { ident } => write!(f, "ext input {ident}"),
+            Self::Rom => write!(f, "ROM mask byte"),
+        }
+    }
+}
+
+impl Label {
+    fn index(self) -> Option<usize> {
+        match self {
+            Self::PowerOnCpu { reg } => CpuReg::ALL.iter().position(|&r| r == reg),
+            Self::PowerOnRiot { reg } => RiotReg::ALL
+                .iter()
+                .position(|&r| r == reg)
+                .map(|i| RIOT_BASE + i),
+            Self::PowerOnRam { addr } => (addr < RAM_SIZE).then_some(RAM_BASE + addr),
+            Self::Input { ident } => line_index(ident).map(|i| LINE_BASE + i),
+            Self::Rom => Some(ROM_INDEX),
+        }
+    }
+
+    fn from_index(index: usize) -> Option<Self> {
+        Some(match index {
+            i if i < RIOT_BASE => Self::PowerOnCpu {
+                reg: CpuReg::ALL[i],
+            },
+            i if i < RAM_BASE => Self::PowerOnRiot {
+                reg: RiotReg::ALL[i - RIOT_BASE],
+            },
+            i if i < LINE_BASE => Self::PowerOnRam { addr: i - RAM_BASE },
+            i if i < ROM_INDEX => Self::Input {
+                ident: line_ident(i - LINE_BASE)?,
+            },
+            ROM_INDEX => Self::Rom,
+            _ => return None,
+        })
+    }
+}
+
+/// A compact set of labels.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct LabelSet([u64; LABEL_WORDS]);
+
+impl LabelSet {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self([0; LABEL_WORDS])
+    }
+
+    #[must_use]
+    pub fn single(label: Label) -> Self {
+        let mut set = Self::new();
+        set.insert(label);
+        set
+    }
+
+    pub fn insert(&mut self, label: Label) {
+        if let Some(i) = label.index() {
+            self.0[i / 64] |= 1 << (i % 64);
+        }
+    }
+
+    #[must_use]
+    pub fn contains(&self, label: Label) -> bool {
+        label
+            .index()
+            .is_some_and(|i| self.0[i / 64] >> (i % 64) & 1 == 1)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Label> + '_ {
+        (0..LABEL_WORDS * 64)
+            .filter(|&i| self.0[i / 64] >> (i % 64) & 1 == 1)
+            .filter_map(Label::from_index)
+    }
+}
+
+impl Combine for LabelSet {
+    fn combine_with(&self, other: &Self) -> Self {
+        Self(array::from_fn(|i| self.0[i] | other.0[i]))
+    }
+}
+
+/// A value along with the labels of everything it could have come from.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Labeled<T> {
+    pub value: T,
+    pub labels: LabelSet,
+}
+
+impl<T: Combine> Combine for Labeled<T> {
+    fn combine_with(&self, other: &Self) -> Self {
+        Self {
+            value: self.value.combine_with(&other.value),
+            labels: self.labels.combine_with(&other.labels),
+        }
+    }
+}
+
+/// Whether a drive could leave its line at more than one level.
+fn is_uncertain(drive: DriveState) -> bool {
+    drive.read().is_some_and(|read| read.as_bool().is_none())
+}
+
+fn is_released(drive: DriveState) -> bool {
+    drive == LineSignal::HighZ.into()
+}
+
+/// Blames the drivers of an Unknown line, or every released driver if the line is floating.
+fn blame(drives: &[(DriveState, LabelSet)]) -> LabelSet {
+    let driving = drives
+        .iter()
+        .filter(|&&(drive, _)| is_uncertain(drive) && !is_released(drive));
+    let floating = drives.iter().filter(|&&(drive, _)| is_released(drive));
+
+    let blamed = driving
+        .clone()
+        .fold(LabelSet::new(), |acc, (_, labels)| acc.combine_with(labels));
+
+    if driving.count() > 0 {
+        blamed
+    } else {
+        floating.fold(LabelSet::new(), |acc, (_, labels)| acc.combine_with(labels))
+    }
+}
+
+/// The CPU registers which can feed each register through the 6502's transfer, ALU and sequencing
+/// paths, in the order of [`CpuReg::ALL`]. Every register can also be loaded from the data bus,
+/// and the instruction cycle selects which path is taken.
+const CPU_SOURCES: [&[CpuReg]; CpuReg::ALL.len()] = {
+    use CpuReg::{A, B, C, D, I, InstrCycle, N, Pc, S, V, X, Y, Z};
+    [
+        &[InstrCycle, N, V, Z, C, Pc],
+        &[InstrCycle, A, X, Y, C, D],
+        &[InstrCycle, X, A, S],
+        &[InstrCycle, Y, A],
+        &[InstrCycle, Pc, N, V, Z, C],
+        &[InstrCycle, S, X],
+        &[InstrCycle, N, A, X, Y, C, D],
+        &[InstrCycle, V, A, C, D],
+        &[InstrCycle, B],
+        &[InstrCycle, D],
+        &[InstrCycle, I],
+        &[InstrCycle, Z, A, X, Y, C, D],
+        &[InstrCycle, C, A, X, Y, D],
+    ]
+};
+
+/// The CPU registers each output can be driven from, as a driver field name and its sources.
+const CPU_OUTPUT_SOURCES: [(&str, &[CpuReg]); 3] = {
+    use CpuReg::{A, B, C, D, I, InstrCycle, N, Pc, S, V, X, Y, Z};
+    [
+        ("a_out", &[InstrCycle, Pc, S, X, Y]),
+        ("db_out", &[InstrCycle, A, X, Y, Pc, N, V, B, D, I, Z, C]),
+        ("rw_out", &[InstrCycle]),
+    ]
+};
+
+/// A shadow map holding the labels of every location in an emulator.
+///
+/// Labels are kept per register, RAM byte and net rather than per Unknown bit, so they are an
+/// over-approximation: a location which changes while holding Unknown bits keeps the labels it
+/// had and gains those of everything it could have been computed from.
+///
+/// Nets take the labels of whichever drivers leave them Unknown, and RIOT locations take the
+/// labels of the data bus. CPU registers take the labels of the data bus and of the registers
+/// which can feed them on some 6502 instruction, whichever instruction is actually running, so a
+/// CPU register is blamed on every register its value could have been derived from.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Provenance {
+    cpu: [LabelSet; CpuReg::ALL.len()],
+    riot: [LabelSet; RiotReg::ALL.len()],
+    ram: [LabelSet; RAM_SIZE],
+    nets: [LabelSet; NET_COUNT],
+}
+
+impl Provenance {
+    /// Labels every location of `emu` which holds Unknown bits as coming from power-on.
+    #[must_use]
+    pub fn power_on(emu: &Emulator) -> Self {
+        let label_if_unknown = |bits: &[SingleRead], label| {
+            if bits.contains(&SingleRead::Unknown) {
+                LabelSet::single(label)
+            } else {
+                LabelSet::new()
+            }
+        };
+
+        Self {
+            cpu: CpuReg::ALL
+                .map(|reg| label_if_unknown(emu.cpu.regs().bits(reg), Label::PowerOnCpu { reg })),
+            riot: RiotReg::ALL
+                .map(|reg| label_if_unknown(emu.riot.regs().bits(reg), Label::PowerOnRiot { reg })),
+            ram: array::from_fn(|addr| {
+                label_if_unknown(&emu.riot.ram()[addr][..], Label::PowerOnRam { addr })
+            }),
+            nets: [LabelSet::new(); _],
+        }
+    }
+
+    /// The labels of the location holding `bit`, if it exists.
+    #[must_use]
+    pub fn labels(&self, bit: StateBit) -> Option<LabelSet> {
+        match bit {
+            StateBit::Cpu { reg, .. } => Label::PowerOnCpu { reg }.index().map(|i| self.cpu[i]),
+            StateBit::Riot { reg, .. } => Label::PowerOnRiot { reg }
+                .index()
+                .map(|i| self.riot[i - RIOT_BASE]),
+            StateBit::Ram { addr, .. } => self.ram.get(addr).copied(),
+            StateBit::Line { ident } => net_index(ident.net_name()).map(|i| self.nets[i]),
+        }
+    }
+
+    /// Reads `bit` from `emu` along with its labels, which are empty if the bit is known.
+    #[must_use]
+    pub fn read(&self, emu: &Emulator, bit: StateBit) -> Option<Labeled<SingleRead>> {
+        let value = emu.bit(bit)?;
+        let labels = if value == SingleRead::Unknown {
+            self.labels(bit)?
+        } else {
+            LabelSet::new()
+        };
+
+        Some(Labeled { value, labels })
+    }
+
+    fn cpu_labels(&self, regs: &[CpuReg]) -> LabelSet {
+        regs.iter()
+            .filter_map(|&reg| Label::PowerOnCpu { reg }.index())
+            .fold(LabelSet::new(), |acc, i| acc.combine_with(&self.cpu[i]))
+    }
+
+    fn update_nets(&mut self, emu: &Emulator, ext: &ExtDrives) {
+        let cpu = |field| {
+            CPU_OUTPUT_SOURCES
+                .iter()
+                .find(|&&(name, _)| name == field)
+                .map_or(LabelSet::new(), |&(_, regs)| self.cpu_labels(regs))
+        };
+        let riot_regs = |regs: &[RiotReg]| {
+            regs.iter()
+                .filter_map(|&reg| Label::PowerOnRiot { reg }.index())
+                .fold(LabelSet::new(), |acc, i| {
+                    acc.combine_with(&self.riot[i - RIOT_BASE])
+                })
+        };
+        let riot_db = emu
+            .line_states
+            .riot_reads()
+            .a
+            .iter_possible_reads()
+            .fold(riot_regs(&RiotReg::ALL), |acc, addr| {
+                acc.combine_with(&self.ram[usize::from(addr)])
+            });
+        let pa = riot_regs(&[RiotReg::Ora, RiotReg::Ddra]);
+        let pb = riot_regs(&[RiotReg::Orb, RiotReg::Ddrb]);
+
+        let rom = LabelSet::single(Label::Rom);
+        let labels = |ident, driver, field| match (driver, field) {
+            (LineDriver::Cpu, _) => cpu(field),
+            (LineDriver::Riot, "db_out") => riot_db,
+            (LineDriver::Riot, "pa_out") => pa,
+            (LineDriver::Riot, "pb_out") => pb,
+            (LineDriver::Riot, _) => riot_regs(&RiotReg::ALL),
+            (LineDriver::Tia, _) => LabelSet::new(),
+            (LineDriver::Cartridge, _) => rom,
+            (LineDriver::External, _) => LabelSet::single(Label::Input { ident }),
+        };
+
+        let drivers = BoardDrivers {
+            cpu: &emu.cpu,
+            riot: &emu.riot,
+            tia: &emu.beam,
+            ext,
+        };
+        let mut nets = [LabelSet::new(); NET_COUNT];
+        drivers.for_each_line(|ident, drives| {
+            let bit = match ident {
+                LineIdent::BusLine { bit, .. } => bit,
+                LineIdent::UniqueLine { .. } => 0,
+            };
+            let unknown = emu
+                .line_states
+                .net(ident.net_name())
+                .and_then(|net| net.get(bit))
+                .is_some_and(|&read| read == SingleRead::Unknown);
+
+            if unknown && let Some(i) = net_index(ident.net_name()) {
+                // A line with nothing driving it is floating on an input the board leaves open.
+                let blamed = if drives.is_empty() {
+                    LabelSet::single(Label::Input { ident })
+                } else {
+                    blame(
+                        &drives
+                            .iter()
+                            .map(|&(driver, field, drive)| (drive, labels(ident, driver, field)))
+                            .collect::<ArrayVec<_, MAX_LINE_DRIVERS>>(),
+                    )
+                };
+                nets[i] = nets[i].combine_with(&blamed);
+            }
+        });
+
+        self.nets = nets;
+    }
+
+    fn update(&mut self, before: &Emulator, after: &Emulator, ext: &ExtDrives) {
+        self.update_nets(after, ext);
+
+        let db = net_index("db").map_or(LabelSet::new(), |i| self.nets[i]);
+        let cpu: [LabelSet; CpuReg::ALL.len()] =
+            array::from_fn(|i| self.cpu_labels(CPU_SOURCES[i]).combine_with(&db));
+
+        let update = |labels: &mut LabelSet, old: &[SingleRead], new: &[SingleRead], input| {
+            if !new.contains(&SingleRead::Unknown) {
+                *labels = LabelSet::new();
+            } else if old != new {
+                *labels = labels.combine_with(input);
+            }
+        };
+
+        for (i, &reg) in CpuReg::ALL.iter().enumerate() {
+            let (old, new) = (before.cpu.regs().bits(reg), after.cpu.regs().bits(reg));
+            update(&mut self.cpu[i], old, new, &cpu[i]);
+        }
+        for (i, &reg) in RiotReg::ALL.iter().enumerate() {
+            let (old, new) = (before.riot.regs().bits(reg), after.riot.regs().bits(reg));
+            update(&mut self.riot[i], old, new, &db);
+        }
+        for (addr, labels) in self.ram.iter_mut().enumerate() {
+            update(
+                labels,
+                &before.riot.ram()[addr][..],
+                &after.riot.ram()[addr][..],
+                &db,
+            );
+        }
+    }
+}
+
+impl Combine for Provenance {
+    fn combine_with(&self, other: &Self) -> Self {
+        Self {
+            cpu: array::from_fn(|i| self.cpu[i].combine_with(&other.cpu[i])),
+            riot: array::from_fn(|i| self.riot[i].combine_with(&other.riot[i])),
+            ram: array::from_fn(|i| self.ram[i].combine_with(&other.ram[i])),
+            nets: array::from_fn(|i| self.nets[i].combine_with(&other.nets[i])),
+        }
+    }
+}
+
+impl Emulator {
+    /// Ticks the emulator, updating `provenance` with where each Unknown value came from.
+    pub fn tick_traced(
+        &mut self,
+        ext: &ExtDrives,
+        provenance: &mut Provenance,
+    ) -> Result<(), TickError> {
+        let before = self.clone();
+        self.tick(ext)?;
+        provenance.update(&before, self, ext);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{line::multi::BusDriveState, read::multi::MultiRead};
+
+    #[test]
+    fn label_index_round_trip() {
+        let labels = [
+            Label::PowerOnCpu { reg: CpuReg::C },
+            Label::PowerOnRiot { reg: RiotReg::Ora },
+            Label::PowerOnRam { addr: 3 },
+            Label::Input {
+                ident: LineIdent::BusLine {
+                    bus_name: "inp1",
+                    bit: 2,
+                },
+            },
+            Label::Input {
+                ident: "rdy".into(),
+            },
+            Label::Rom,
+        ];
+
+        let set = labels.iter().fold(LabelSet::new(), |acc, &label| {
+            acc.combine_with(&LabelSet::single(label))
+        });
+        assert!(set.iter().eq(labels));
+    }
+
+    #[test]
+    fn blames_uncertain_drivers() {
+        let mut emu = Emulator::new();
+        let mut provenance = Provenance::power_on(&emu);
+        assert!(
+            provenance
+                .labels(StateBit::Ram { addr: 3, bit: 0 })
+                .unwrap()
+                .contains(Label::PowerOnRam { addr: 3 })
+        );
+
+        emu.line_states.a = MultiRead::from_value(0);
+        emu.line_states.db = MultiRead::from_value(0);
+        emu.line_states.db[0] = SingleRead::Unknown;
+        emu.cpu.a_out = BusDriveState::from_value(0);
+        emu.cpu.db_out = BusDriveState::from_signals(&[LineSignal::HighZ; _]);
+        emu.riot.db_out = BusDriveState::from_signals(&[LineSignal::HighZ; _]);
+
+        let mut ext = ExtDrives::new();
+        ext.db[0] = SingleRead::Unknown.into();
+        provenance.update_nets(&emu, &ext);
+
+        let db0 = provenance
+            .read(
+                &emu,
+                StateBit::Line {
+                    ident: LineIdent::BusLine {
+                        bus_name: "db",
+                        bit: 0,
+                    },
+                },
+            )
+            .unwrap();
+        assert!(db0.labels.iter().eq([Label::Rom]));
+
+        let a1 = provenance.read(&emu, StateBit::bus("a", 13).nth(1).unwrap());
+        assert!(a1.unwrap().labels.is_empty());
+    }
+
+    #[test]
+    fn traces_cpu_registers_through_their_sources() {
+        let mut before = Emulator::new();
+        before.line_states.a = MultiRead::from_value(0);
+        before.line_states.db = MultiRead::from_value(0);
+        for reg in CpuReg::ALL {
+            if ![CpuReg::A, CpuReg::X].contains(&reg) {
+                before.cpu.regs_mut().bits_mut(reg).fill(SingleRead::Low);
+            }
+        }
+        let mut provenance = Provenance::power_on(&before);
+
+        // TAY leaves Y Unknown through A, whatever X holds.
+        let mut after = before.clone();
+        after.cpu.regs_mut().y = after.cpu.regs().a.clone();
+        provenance.update(&before, &after, &ExtDrives::new());
+
+        let y = provenance.read(
+            &after,
+            StateBit::Cpu {
+                reg: CpuReg::Y,
+                bit: 0,
+            },
+        );
+        assert!(
+            y.unwrap()
+                .labels
+                .iter()
+                .eq([Label::PowerOnCpu { reg: CpuReg::A }])
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use crate::{
+    common::codec::{CodecError, Reader, Writer},
+    full::{
+        Emulator,
+        save_state::{SAVE_STATE_MAX_LEN, SaveStateError},
+    },
+};
+use arrayvec::ArrayVec;
+
+/// Each delta is at most one and a half times the size of a save state, plus its headers.
+const DELTA_MAX_LEN: usize = SAVE_STATE_MAX_LEN * 2;
+
+type Snapshot = ArrayVec<u8, SAVE_STATE_MAX_LEN>;
+
+/// Encodes the changes that turn `base` into `target`, as runs of unchanged bytes followed by runs
+/// of changed bytes stored as their XOR with `base`.
+fn encode_delta(base: &[u8], target: &[u8], out: &mut [u8]) -> Result<usize, CodecError> {
+    let mut w = Writer::new(out);
+    w.write_varint(target.len() as u64)?;
+
+    let xor_at = |i: usize| target[i] ^ base.get(i).copied().unwrap_or(0);
+    let mut i = 0;
+
+    while i < target.len() {
+        let unchanged = (i..target.len()).take_while(|&j| xor_at(j) == 0).count();
+        i += unchanged;
+
+        let changed = (i..target.len()).take_while(|&j| xor_at(j) != 0).count();
+        w.write_varint(unchanged as u64)?;
+        w.write_varint(changed as u64)?;
+
+        for j in i..i + changed {
+            w.write_u8(xor_at(j))?;
+        }
+        i += changed;
+    }
+
+    Ok(w.position())
+}
+
+fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Snapshot, CodecError> {
+    let mut r = Reader::new(delta);
+    let len = usize::try_from(r.read_varint()?).map_err(|_| CodecError::InvalidValue)?;
+
+    let mut res = Snapshot::new();
+    for i in 0..len {
+        res.try_push(base.get(i).copied().unwrap_or(0))
+            .map_err(|_| CodecError::BufferFull)?;
+    }
+
+    let mut i = 0;
+    while !r.is_empty() {
+        i += usize::try_from(r.read_varint()?).map_err(|_| CodecError::InvalidValue)?;
+        let changed = r.read_varint()?;
+
+        for _ in 0..changed {
+            *res.get_mut(i).ok_or(CodecError::InvalidValue)? ^= r.read_u8()?;
+            i += 1;
+        }
+    }
+
+    Ok(res)
+}
+
+/// A bounded history of emulator snapshots for rewinding gameplay.
+///
+/// The newest snapshot is stored in full, and each older one as a delta against the snapshot taken
+/// after it, so the oldest snapshot can be dropped without touching the rest. The deltas share a
+/// ring of `BYTES` bytes and at most `FRAMES` of them are kept, so the oldest are dropped whenever
+/// a new delta does not fit.
+pub struct Rewind<const BYTES: usize, const FRAMES: usize> {
+    interval: u64,
+    last_frame: Option<u64>,
+    newest: Option<Snapshot>,
+    bytes: [u8; BYTES],
+    /// The offset of the oldest delta in `bytes`.
+    head: usize,
+    /// The number of bytes taken by the deltas, starting at `head`.
+    used: usize,
+    /// The length of each delta, starting at `oldest`.
+    lens: [usize; FRAMES],
+    oldest: usize,
+    count: usize,
+}
+
+impl<const BYTES: usize, const FRAMES: usize> Rewind<BYTES, FRAMES> {
+    /// Creates a rewind buffer that snapshots every `interval` frames.
+    #[must_use]
+    pub const fn new(interval: u64) -> Self {
+        Self {
+            interval: if interval == 0 { 1 } else { interval },
+            last_frame: None,
+            newest: None,
+            bytes: [0; BYTES],
+            head: 0,
+            used: 0,
+            lens: [0; FRAMES],
+            oldest: 0,
+            count: 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        if self.newest.is_some() {
+            self.count + 1
+        } else {
+            0
+        }
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.newest.is_none()
+    }
+
+    /// The number of bytes of the ring taken by deltas.
+    #[must_use]
+    pub const fn bytes_used(&self) -> usize {
+        self.used
+    }
+
+    /// Takes a snapshot the first time it is called on a frame on the snapshot interval, so it can
+    /// be called after every tick.
+    pub fn record_frame(&mut self, emu: &Emulator) -> Result<(), SaveStateError> {
+        let frame = emu.frame();
+
+        if frame.is_multiple_of(self.interval) && self.last_frame != Some(frame) {
+            self.push(emu)?;
+            self.last_frame = Some(frame);
+        }
+
+        Ok(())
+    }
+
+    /// Takes a snapshot, leaving the buffer as it was if that fails. A delta too long for the
+    /// whole ring drops every older snapshot.
+    pub fn push(&mut self, emu: &Emulator) -> Result<(), SaveStateError> {
+        let mut buf = [0; SAVE_STATE_MAX_LEN];
+        let len = emu.save_state(&mut buf)?;
+        let snapshot: Snapshot = buf[..len].try_into().map_err(|_| CodecError::BufferFull)?;
+
+        if FRAMES > 0
+            && let Some(prev) = &self.newest
+        {
+            let mut delta = [0; DELTA_MAX_LEN];
+            let delta_len = encode_delta(&snapshot, prev, &mut delta)?;
+
+            while self.count > 0 && (self.count == FRAMES || self.used + delta_len > BYTES) {
+                self.drop_oldest();
+            }
+
+            if delta_len <= BYTES {
+                self.write_at((self.head + self.used) % BYTES, &delta[..delta_len]);
+                self.lens[(self.oldest + self.count) % FRAMES] = delta_len;
+                self.used += delta_len;
+                self.count += 1;
+            }
+        }
+
+        self.newest = Some(snapshot);
+        Ok(())
+    }
+
+    /// Removes and restores the newest snapshot, so repeated calls step further back in time.
+    /// The buffer is left as it was if that fails.
+    pub fn rewind(&mut self) -> Result<Option<Emulator>, SaveStateError> {
+        let Some(newest) = &self.newest else {
+            return Ok(None);
+        };
+
+        let emu = Emulator::load_state(newest)?;
+        let older = if self.count == 0 {
+            None
+        } else {
+            let len = self.lens[(self.oldest + self.count - 1) % FRAMES];
+            let mut delta = [0; DELTA_MAX_LEN];
+            self.read_at((self.head + self.used - len) % BYTES, &mut delta[..len]);
+
+            let older = apply_delta(newest, &delta[..len])?;
+            self.used -= len;
+            self.count -= 1;
+            Some(older)
+        };
+
+        self.newest = older;
+        self.last_frame = None;
+        Ok(Some(emu))
+    }
+
+    pub fn clear(&mut self) {
+        self.last_frame = None;
+        self.newest = None;
+        self.head = 0;
+        self.used = 0;
+        self.oldest = 0;
+        self.count = 0;
+    }
+
+    const fn drop_oldest(&mut self) {
+        let len = self.lens[self.oldest];
+        self.head = (self.head + len) % BYTES;
+        self.used -= len;
+        self.oldest = (self.oldest + 1) % FRAMES;
+        self.count -= 1;
+    }
+
+    /// Copies `data` into the ring at `at`, wrapping around its end.
+    fn write_at(&mut self, at: usize, data: &[u8]) {
+        let (first, rest) = data.split_at(data.len().min(BYTES - at));
+        self.bytes[at..at + first.len()].copy_from_slice(first);
+        self.bytes[..rest.len()].copy_from_slice(rest);
+    }
+
+    /// Fills `out` from the ring at `at`, wrapping around its end.
+    fn read_at(&self, at: usize, out: &mut [u8]) {
+        let (first, rest) = out.split_at_mut(out.len().min(BYTES - at));
+        first.copy_from_slice(&self.bytes[at..at + first.len()]);
+        rest.copy_from_slice(&self.bytes[..rest.len()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::line::multi::BusDriveState;
+
+    fn states() -> impl Iterator<Item = Emulator> {
+        (0..6).map(|i| {
+            let mut emu = Emulator::new();
+            emu.riot.pa_out = BusDriveState::from_value(i * 37);
+            emu.cycles = u64::from(i) * 1000;
+            emu
+        })
+    }
+
+    #[test]
+    fn delta_round_trip() {
+        let base = [1, 2, 3, 4, 5, 6];
+        let target = [1, 2, 9, 4, 5, 7, 8];
+        let mut out = [0; 32];
+        let len = encode_delta(&base, &target, &mut out).unwrap();
+
+        assert_eq!(apply_delta(&base, &out[..len]).unwrap().as_slice(), target);
+    }
+
+    #[test]
+    fn rewinds_newest_first() {
+        let mut rewind = Rewind::<4096, 8>::new(1);
+        for emu in states() {
+            rewind.push(&emu).unwrap();
+        }
+
+        for emu in states().collect::<ArrayVec<_, 6>>().into_iter().rev() {
+            assert_eq!(rewind.rewind().unwrap(), Some(emu));
+        }
+        assert_eq!(rewind.rewind().unwrap(), None);
+    }
+
+    #[test]
+    fn records_once_per_frame() {
+        let mut rewind = Rewind::<4096, 4>::new(1);
+        let mut emu = Emulator::new();
+
+        for _ in 0..3 {
+            rewind.record_frame(&emu).unwrap();
+            emu.beam.advance_cycle();
+        }
+        assert_eq!(rewind.len(), 1);
+
+        let frame = emu.frame();
+        while emu.frame() == frame {
+            emu.beam.advance_cycle();
+        }
+        rewind.record_frame(&emu).unwrap();
+        rewind.record_frame(&emu).unwrap();
+        assert_eq!(rewind.len(), 2);
+    }
+
+    #[test]
+    fn drops_oldest_when_full() {
+        let mut rewind = Rewind::<4096, 2>::new(1);
+        for emu in states() {
+            rewind.push(&emu).unwrap();
+        }
+        assert_eq!(rewind.len(), 3);
+
+        let expected: ArrayVec<_, 6> = states().collect();
+        for emu in expected[3..].iter().rev() {
+            assert_eq!(rewind.rewind().unwrap().as_ref(), Some(emu));
+        }
+        assert!(rewind.is_empty());
+    }
+
+    #[test]
+    fn drops_oldest_when_out_of_bytes() {
+        let mut rewind = Rewind::<48, 16>::new(1);
+        for emu in states() {
+            rewind.push(&emu).unwrap();
+            assert!(rewind.bytes_used() <= 48);
+        }
+
+        let kept = rewind.len();
+        assert!((2..6).contains(&kept));
+
+        let expected: ArrayVec<_, 6> = states().collect();
+        for emu in expected[6 - kept..].iter().rev() {
+            assert_eq!(rewind.rewind().unwrap().as_ref(), Some(emu));
+        }
+        assert!(rewind.is_empty());
+    }
+}
@@ -0,0 +1,389 @@
+use crate::{
+    common::codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer, section_max_len},
+    cpu::Cpu,
+    full::{
+        Emulator, beam::Beam, bus_hold::BusHoldState, contention::Contention,
+        line_reads::EmuLineStates,
+    },
+    riot::Riot,
+};
+use core::{mem, num::NonZeroU32};
+use thiserror::Error;
+
+const MAGIC: [u8; 4] = *b"E26S";
+
+/// The current save state format version.
+///
+/// The state is stored as tagged sections, one per component, each of which must appear exactly
+/// once. Sections this emulator does not know are skipped. When the encoding of a section
+/// changes, bump this version and keep decoding the old layout for older versions in
+/// [`decode_section`].
+pub const SAVE_STATE_VERSION: u16 = 3;
+
+/// The first version whose timing section ends with the settle limit. Older saves keep the
+/// default limit.
+const VERSION_SETTLE_LIMIT: u16 = 2;
+
+/// The first version whose timing section ends with the beam's VSYNC and WSYNC state. Older saves
+/// load with the beam free-running.
+const VERSION_BEAM_SYNC: u16 = 3;
+
+/// An upper bound on the size of an encoded save state, from the longest encoding of each section.
+pub const SAVE_STATE_MAX_LEN: usize = MAGIC.len()
+    + 2
+    + section_max_len(Cpu::MAX_ENCODED_LEN)
+    + section_max_len(Riot::MAX_ENCODED_LEN)
+    + section_max_len(EmuLineStates::MAX_ENCODED_LEN)
+    + section_max_len(1 + 8 + Beam::MAX_ENCODED_LEN + 4 + 1)
+    + section_max_len(BusHoldState::MAX_ENCODED_LEN)
+    + section_max_len(Contention::MAX_ENCODED_LEN);
+
+const CPU_SECTION: u8 = 1;
+const RIOT_SECTION: u8 = 2;
+const LINES_SECTION: u8 = 3;
+const TIMING_SECTION: u8 = 4;
+const BUS_HOLD_SECTION: u8 = 5;
+const CONTENTION_SECTION: u8 = 6;
+
+/// Every section a save state must hold.
+const SECTIONS: [u8; 6] = [
+    CPU_SECTION,
+    RIOT_SECTION,
+    LINES_SECTION,
+    TIMING_SECTION,
+    BUS_HOLD_SECTION,
+    CONTENTION_SECTION,
+];
+
+#[derive(Clone, Copy, Debug, Eq, Error, Hash, PartialEq)]
+pub enum SaveStateError {
+    #[error("cannot process save state data: {0}")]
+    Codec(#[from] CodecError),
+
+    #[error("cannot load save state as it does not start with the save state signature")]
+    BadMagic,
+
+    #[error("cannot load save state as format version {version} is not supported")]
+    UnsupportedVersion { version: u16 },
+
+    #[error("cannot load save state as it has no section with tag {tag}")]
+    MissingSection { tag: u8 },
+
+    #[error("cannot load save state as it has more than one section with tag {tag}")]
+    DuplicateSection { tag: u8 },
+}
+
+/// Decodes a known section written in format `version`.
+///
+/// Each section only sets the fields it holds, so the sections can be decoded in any order.
+fn decode_section(
+    emu: &mut Emulator,
+    version: u16,
+    tag: u8,
+    r: &mut Reader<'_>,
+) -> Result<(), CodecError> {
+    match tag {
+        CPU_SECTION => emu.cpu = Decode::decode(r)?,
+        RIOT_SECTION => emu.riot = Decode::decode(r)?,
+        LINES_SECTION => {
+            let hold = mem::take(&mut emu.line_states.hold);
+            emu.line_states = Decode::decode(r)?;
+            emu.line_states.hold = hold;
+        }
+        TIMING_SECTION => {
+            emu.phi0 = match r.read_u8()? {
+                0 => false,
+                1 => true,
+                _ => return Err(CodecError::InvalidValue),
+            };
+            emu.cycles = r.read_u64()?;
+            emu.beam = Decode::decode(r)?;
+
+            if version >= VERSION_SETTLE_LIMIT {
+                emu.settle_limit =
+                    NonZeroU32::new(r.read_u32()?).ok_or(CodecError::InvalidValue)?;
+            }
+            if version >= VERSION_BEAM_SYNC {
+                emu.beam.decode_sync(r)?;
+            }
+        }
+        BUS_HOLD_SECTION => emu.line_states.hold = Decode::decode(r)?,
+        CONTENTION_SECTION => emu.contention = Decode::decode(r)?,
+        _ => (),
+    }
+
+    Ok(())
+}
+
+impl Emulator {
+    /// Saves the complete state of the emulator, returning the number of bytes written.
+    pub fn save_state(&self, buf: &mut [u8]) -> Result<usize, SaveStateError> {
+        let mut w = Writer::new(buf);
+        w.write_bytes(&MAGIC)?;
+        w.write_u16(SAVE_STATE_VERSION)?;
+
+        w.write_section(CPU_SECTION, |w| self.cpu.encode(w))?;
+        w.write_section(RIOT_SECTION, |w| self.riot.encode(w))?;
+        w.write_section(LINES_SECTION, |w| self.line_states.encode(w))?;
+        w.write_section(TIMING_SECTION, |w| {
+            w.write_u8(u8::from(self.phi0))?;
+            w.write_u64(self.cycles)?;
+            self.beam.encode(w)?;
+            w.write_u32(self.settle_limit.get())?;
+            self.beam.encode_sync(w)
+        })?;
+        w.write_section(BUS_HOLD_SECTION, |w| self.line_states.hold.encode(w))?;
+        w.write_section(CONTENTION_SECTION, |w| self.contention.encode(w))?;
+
+        Ok(w.position())
+    }
+
+    pub fn load_state(data: &[u8]) -> Result<Self, SaveStateError> {
+        let mut r = Reader::new(data);
+
+        if r.read_bytes()? != MAGIC {
+            return Err(SaveStateError::BadMagic);
+        }
+
+        let version = r.read_u16()?;
+        if version == 0 || version > SAVE_STATE_VERSION {
+            return Err(SaveStateError::UnsupportedVersion { version });
+        }
+
+        let mut emu = Self::new();
+        let mut seen = [false; SECTIONS.len()];
+        while !r.is_empty() {
+            let (tag, mut section) = r.read_section()?;
+            let Some(i) = SECTIONS.iter().position(|&known| known == tag) else {
+                continue;
+            };
+
+            if mem::replace(&mut seen[i], true) {
+                return Err(SaveStateError::DuplicateSection { tag });
+            }
+
+            decode_section(&mut emu, version, tag, &mut section)?;
+            section.finish()?;
+        }
+
+        if let Some(i) = seen.iter().position(|&seen| !seen) {
+            return Err(SaveStateError::MissingSection { tag: SECTIONS[i] });
+        }
+
+        Ok(emu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{
+            line::multi::BusDriveState,
+            read::{multi::MultiRead, single::SingleRead},
+            signal::LineSignal,
+        },
+        full::{bus_hold::BusHold, contention::ContentionPolicy, settle::DEFAULT_SETTLE_LIMIT},
+    };
+    use arrayvec::ArrayVec;
+
+    /// A modified emulator whose beam is still free-running.
+    fn unsynced() -> Emulator {
+        let mut emu = Emulator::new();
+        emu.contention.policy = ContentionPolicy::ResolveAndLog;
+        emu.contention
+            .contend([true.into(), false.into()].into_iter(), "rw".into(), 7)
+            .unwrap();
+        emu.riot.pa_out = BusDriveState::from_value(0x5a);
+        emu.riot.pb_out[2] = LineSignal::HighZ.into();
+        emu.cpu.rw_out = LineSignal::High.into();
+        emu.line_states.rw = SingleRead::Low;
+        // Turns on VSYNC in `modified`.
+        emu.line_states.a = MultiRead::from_value(0x0000);
+        emu.line_states.db = MultiRead::from_value(0x02);
+        emu.set_bus_hold("D", Some(BusHold { decay: Some(4) }));
+        emu.settle_limit = NonZeroU32::new(3).unwrap();
+        emu.cycles = 12_345;
+        for _ in 0..emu.cycles {
+            emu.beam.advance_cycle();
+        }
+        emu
+    }
+
+    fn modified() -> Emulator {
+        let mut emu = unsynced();
+        emu.beam.observe_bus(&emu.line_states);
+        emu
+    }
+
+    /// Saves `emu` as an older `version`, whose timing section lacks the last `cut` bytes.
+    fn save_old(emu: &Emulator, version: u16, cut: usize, buf: &mut [u8]) -> usize {
+        let mut saved = [0; SAVE_STATE_MAX_LEN];
+        let len = emu.save_state(&mut saved).unwrap();
+
+        let mut parts = sections(&saved[..len]);
+        for (tag, payload) in &mut parts {
+            if *tag == TIMING_SECTION {
+                *payload = &payload[..payload.len() - cut];
+            }
+        }
+
+        assemble(buf, version, &parts)
+    }
+
+    type Sections<'a> = ArrayVec<(u8, &'a [u8]), 8>;
+
+    fn sections(data: &[u8]) -> Sections<'_> {
+        let mut r = Reader::new(&data[MAGIC.len() + 2..]);
+        let mut res = Sections::new();
+
+        while !r.is_empty() {
+            let tag = r.read_u8().unwrap();
+            let len = r.read_u16().unwrap();
+            res.push((tag, r.read_slice(usize::from(len)).unwrap()));
+        }
+
+        res
+    }
+
+    fn assemble(buf: &mut [u8], version: u16, sections: &[(u8, &[u8])]) -> usize {
+        let mut w = Writer::new(buf);
+        w.write_bytes(&MAGIC).unwrap();
+        w.write_u16(version).unwrap();
+
+        for &(tag, payload) in sections {
+            w.write_section(tag, |w| w.write_bytes(payload)).unwrap();
+        }
+
+        w.position()
+    }
+
+    fn load_edited(
+        emu: &Emulator,
+        edit: impl FnOnce(&mut Sections<'_>),
+    ) -> Result<Emulator, SaveStateError> {
+        let mut saved = [0; SAVE_STATE_MAX_LEN];
+        let len = emu.save_state(&mut saved).unwrap();
+        let mut parts = sections(&saved[..len]);
+        edit(&mut parts);
+
+        let mut buf = [0; SAVE_STATE_MAX_LEN + 8];
+        let len = assemble(&mut buf, SAVE_STATE_VERSION, &parts);
+        Emulator::load_state(&buf[..len])
+    }
+
+    #[test]
+    fn round_trip() {
+        let emu = modified();
+        let mut buf = [0; SAVE_STATE_MAX_LEN];
+        let len = emu.save_state(&mut buf).unwrap();
+
+        assert_eq!(Emulator::load_state(&buf[..len]).unwrap(), emu);
+    }
+
+    #[test]
+    fn skips_unknown_sections() {
+        let emu = modified();
+        let mut buf = [0; SAVE_STATE_MAX_LEN + 8];
+        let mut len = emu.save_state(&mut buf).unwrap();
+
+        let mut w = Writer::new(&mut buf[len..]);
+        w.write_section(0xff, |w| w.write_u64(0)).unwrap();
+        len += w.position();
+
+        assert_eq!(Emulator::load_state(&buf[..len]).unwrap(), emu);
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let mut buf = [0; SAVE_STATE_MAX_LEN];
+        let len = Emulator::new().save_state(&mut buf).unwrap();
+        buf[4..6].copy_from_slice(&(SAVE_STATE_VERSION + 1).to_le_bytes());
+
+        assert_eq!(
+            Emulator::load_state(&buf[..len]),
+            Err(SaveStateError::UnsupportedVersion {
+                version: SAVE_STATE_VERSION + 1
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_version_zero() {
+        let mut buf = [0; SAVE_STATE_MAX_LEN];
+        let len = Emulator::new().save_state(&mut buf).unwrap();
+        buf[4..6].copy_from_slice(&0u16.to_le_bytes());
+
+        assert_eq!(
+            Emulator::load_state(&buf[..len]),
+            Err(SaveStateError::UnsupportedVersion { version: 0 })
+        );
+    }
+
+    #[test]
+    fn loads_sections_in_any_order() {
+        let emu = modified();
+
+        assert_eq!(load_edited(&emu, |parts| parts.reverse()), Ok(emu));
+    }
+
+    #[test]
+    fn rejects_missing_and_duplicate_sections() {
+        let emu = modified();
+
+        assert_eq!(
+            load_edited(&emu, |parts| {
+                parts.remove(1);
+            }),
+            Err(SaveStateError::MissingSection { tag: RIOT_SECTION })
+        );
+        assert_eq!(
+            load_edited(&emu, |parts| parts.push(parts[0])),
+            Err(SaveStateError::DuplicateSection { tag: CPU_SECTION })
+        );
+    }
+
+    #[test]
+    fn rejects_trailing_section_bytes() {
+        let mut cpu = [0; Cpu::MAX_ENCODED_LEN + 1];
+        let mut saved = [0; SAVE_STATE_MAX_LEN];
+        let len = modified().save_state(&mut saved).unwrap();
+        let mut parts = sections(&saved[..len]);
+
+        let (tag, payload) = parts[0];
+        cpu[..payload.len()].copy_from_slice(payload);
+        parts[0] = (tag, &cpu[..=payload.len()]);
+
+        let mut buf = [0; SAVE_STATE_MAX_LEN + 1];
+        let len = assemble(&mut buf, SAVE_STATE_VERSION, &parts);
+
+        assert_eq!(
+            Emulator::load_state(&buf[..len]),
+            Err(SaveStateError::Codec(CodecError::TrailingBytes))
+        );
+    }
+
+    #[test]
+    fn migrates_version_1() {
+        // Version 1 timing sections end before the settle limit.
+        let mut buf = [0; SAVE_STATE_MAX_LEN];
+        let len = save_old(&modified(), 1, 4 + 1, &mut buf);
+
+        assert_eq!(
+            Emulator::load_state(&buf[..len]),
+            Ok(Emulator {
+                settle_limit: DEFAULT_SETTLE_LIMIT,
+                ..unsynced()
+            })
+        );
+    }
+
+    #[test]
+    fn migrates_version_2() {
+        // Version 2 timing sections end before the beam's sync state.
+        let mut buf = [0; SAVE_STATE_MAX_LEN];
+        let len = save_old(&modified(), 2, 1, &mut buf);
+
+        assert_eq!(Emulator::load_state(&buf[..len]), Ok(unsynced()));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::full::{
+    Emulator,
+    ext_drives::ExtDrives,
+    line_reads::{NET_COUNT, NETS},
+    tick::{TickError, TickStage},
+};
+use core::{fmt, num::NonZeroU32};
+
+/// The number of update passes allowed for the lines to settle before each edge.
+pub const DEFAULT_SETTLE_LIMIT: NonZeroU32 = NonZeroU32::new(8).unwrap();
+
+const _: () = assert!(NET_COUNT <= u64::BITS as usize);
+
+/// A set of nets, by their index in [`NETS`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct NetSet(u64);
+
+impl NetSet {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[must_use]
+    pub const fn contains(self, net: usize) -> bool {
+        net < NET_COUNT && self.0 >> net & 1 == 1
+    }
+
+    pub const fn insert(&mut self, net: usize) {
+        self.0 |= 1 << net;
+    }
+
+    /// The nets whose flag is set, by their index in [`NETS`].
+    #[must_use]
+    pub fn from_flags(flags: &[bool; NET_COUNT]) -> Self {
+        let mut set = Self::new();
+        for (net, _) in flags.iter().enumerate().filter(|&(_, &flag)| flag) {
+            set.insert(net);
+        }
+        set
+    }
+
+    #[must_use]
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// The names of the nets in the set.
+    pub fn names(self) -> impl Iterator<Item = &'static str> {
+        NETS.iter()
+            .enumerate()
+            .filter(move |&(i, _)| self.contains(i))
+            .map(|(_, net)| net.name)
+    }
+}
+
+impl fmt::Display for NetSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, name) in self.names().enumerate() {
+            let sep = if i == 0 { "" } else { ", " };
+            write!(f, "{sep}{name}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Emulator {
+    /// Updates the lines and recomputes the outputs which follow them until neither changes,
+    /// giving up after the settle limit.
+    pub(super) fn settle(&mut self, ext: &ExtDrives, stage: TickStage) -> Result<(), TickError> {
+        self.settle_with(ext, stage, Self::update_outputs)
+    }
+
+    /// Settles with `outputs` recomputing the combinational outputs from the lines, returning
+    /// whether any of them changed.
+    ///
+    /// On failure, the nets reported are those which changed on every pass after the first, as
+    /// the first pass only follows the outputs set by the previous edge.
+    fn settle_with(
+        &mut self,
+        ext: &ExtDrives,
+        stage: TickStage,
+        mut outputs: impl FnMut(&mut Self, TickStage) -> bool,
+    ) -> Result<(), TickError> {
+        let mut unsettled = None;
+
+        for pass in 0..self.settle_limit.get() {
+            let changed = self
+                .update(ext, stage.edge())
+                .map_err(|source| TickError::Line { stage, source })?;
+
+            if !outputs(self, stage) {
+                return Ok(());
+            }
+
+            unsettled = Some(match unsettled {
+                Some(nets) if pass > 1 => changed.intersection(nets),
+                _ => changed,
+            });
+        }
+
+        Err(TickError::Unsettled {
+            stage,
+            nets: unsettled.unwrap_or_default(),
+            iterations: self.settle_limit.get(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{
+        line::{multi::BusDriveState, single::DriveState},
+        read::{multi::MultiRead, single::SingleRead},
+        signal::LineSignal,
+    };
+
+    /// Feeds `inp2` back into itself through the RIOT's port A pins, each driving the value of
+    /// the line above it, so a value on the top line takes a pass per bit to shift down.
+    fn shift_down(emu: &mut Emulator, _stage: TickStage) -> bool {
+        let before = emu.riot.pa_out.clone();
+        for bit in 0..4 {
+            emu.riot.pa_out[bit] = DriveState::from(emu.line_states.inp2[bit + 1]);
+        }
+        emu.riot.pa_out != before
+    }
+
+    fn ext() -> ExtDrives {
+        let mut ext = ExtDrives::new();
+        ext.inp2[4] = LineSignal::Low.into();
+        ext
+    }
+
+    #[test]
+    fn riot_read_timing() {
+        let released = BusDriveState::from_signals(&[LineSignal::HighZ; 8]);
+        let mut emu = Emulator::new();
+        emu.riot.ram_mut()[5] = MultiRead::from_value(0x3c);
+        emu.riot.ram_mut()[6] = MultiRead::from_value(0x5a);
+        emu.cpu.a_out = BusDriveState::from_value(1 << 11 | 5);
+        emu.cpu.rw_out = LineSignal::High.into();
+
+        // While phi2 is low the RIOT leaves the data bus alone.
+        emu.settle(&ExtDrives::new(), TickStage::CpuRisingEdge)
+            .unwrap();
+        assert_eq!(emu.riot.db_out, released);
+
+        // From the rising edge it answers the read, and follows the address until the falling
+        // edge, when the CPU samples the data bus.
+        emu.riot.handle_rising_edge(emu.line_states.riot_reads());
+        assert_eq!(emu.riot.db_out, BusDriveState::from_value(0x3c));
+        emu.cpu.a_out = BusDriveState::from_value(1 << 11 | 6);
+        emu.settle(&ExtDrives::new(), TickStage::CpuFallingEdge)
+            .unwrap();
+        assert_eq!(emu.line_states.db, MultiRead::from_value(0x5a));
+
+        emu.riot.handle_falling_edge();
+        emu.settle(&ExtDrives::new(), TickStage::CpuRisingEdge)
+            .unwrap();
+        assert_eq!(emu.riot.db_out, released);
+    }
+
+    #[test]
+    fn settles_through_feedback() {
+        let mut emu = Emulator::new();
+        emu.settle_with(&ext(), TickStage::CpuFallingEdge, shift_down)
+            .unwrap();
+        assert_eq!(emu.line_states.inp2[0], SingleRead::Low);
+
+        let mut emu = Emulator::new();
+        emu.settle_limit = NonZeroU32::new(3).unwrap();
+        let err = emu
+            .settle_with(&ext(), TickStage::CpuFallingEdge, shift_down)
+            .unwrap_err();
+        assert!(matches!(err, TickError::Unsettled { iterations: 3, .. }));
+    }
+
+    #[test]
+    fn reports_oscillating_nets() {
+        let mut emu = Emulator::new();
+        let inverter = |emu: &mut Emulator, _| {
+            let before = emu.riot.pa_out[0];
+            let read = emu.line_states.inp2[0];
+            emu.riot.pa_out[0] =
+                DriveState::from(read.as_bool().map_or(read, |value| (!value).into()));
+            emu.riot.pa_out[0] != before
+        };
+
+        let err = emu
+            .settle_with(&ExtDrives::new(), TickStage::CpuFallingEdge, inverter)
+            .unwrap_err();
+
+        let TickError::Unsettled { nets, .. } = err else {
+            panic!("expected unsettled nets, got {err:?}");
+        };
+        let mut names = arrayvec::ArrayString::<128>::new();
+        fmt::write(&mut names, format_args!("{nets}")).unwrap();
+        assert_eq!(names.as_str(), "inp2");
+    }
+}
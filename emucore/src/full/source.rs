@@ -0,0 +1,39 @@
+use crate::full::{Emulator, ext_drives::ExtDrives, joystick::Joystick};
+use core::ops::Range;
+
+/// Supplies the external drives for each tick of a run.
+pub trait DriveSource {
+    fn drives(&mut self, emu: &Emulator) -> ExtDrives;
+}
+
+impl DriveSource for ExtDrives {
+    fn drives(&mut self, _: &Emulator) -> ExtDrives {
+        self.clone()
+    }
+}
+
+impl<F: FnMut(&Emulator) -> ExtDrives> DriveSource for F {
+    fn drives(&mut self, emu: &Emulator) -> ExtDrives {
+        self(emu)
+    }
+}
+
+/// Wraps a source so that both joysticks read as Unknown during a range of frames, letting a single
+/// run cover every input the player could have given.
+pub struct UnknownInputs<S> {
+    pub source: S,
+    pub frames: Range<u64>,
+}
+
+impl<S: DriveSource> DriveSource for UnknownInputs<S> {
+    fn drives(&mut self, emu: &Emulator) -> ExtDrives {
+        let mut drives = self.source.drives(emu);
+
+        if self.frames.contains(&emu.frame()) {
+            Joystick::drive_unknown(&mut drives.inp1);
+            Joystick::drive_unknown(&mut drives.inp2);
+        }
+
+        drives
+    }
+}
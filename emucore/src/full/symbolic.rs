@@ -0,0 +1,131 @@
+use crate::{
+    common::{
+        bdd::{Bdd, BddError, BddRef},
+        read::single::SingleRead,
+    },
+    full::{
+        Emulator,
+        bits::{STATE_BIT_COUNT, StateBit},
+    },
+};
+
+/// An emulator state whose Unknown bits are backed by [`Bdd`] variables, so conditions over
+/// several bits keep their correlations.
+///
+/// This is a view of a single state, not a symbolic mode of the emulator: ticking still steps per
+/// bit, and no formula survives a tick. Callers build conditions over the bits of the state with
+/// [`Symbolic::bdd`] and ask whether and how they can hold. Carrying formulas through the line
+/// and register updates needs those updates to work on [`BddRef`]s as well as [`SingleRead`]s,
+/// which waits on the CPU's edge handlers.
+#[derive(Clone, Debug)]
+pub struct Symbolic<const NODES: usize> {
+    emu: Emulator,
+    bdd: Bdd<NODES>,
+    /// The variable of each Unknown bit, by [`StateBit::index`].
+    vars: [u16; STATE_BIT_COUNT],
+}
+
+impl<const NODES: usize> Symbolic<NODES> {
+    /// Takes a symbolic view of `emu`, where variable `i` is its `i`th Unknown bit in
+    /// [`StateBit::all`] order.
+    pub fn new(emu: &Emulator) -> Result<Self, BddError> {
+        let mut vars = [0; STATE_BIT_COUNT];
+        let mut count = 0;
+
+        for (i, bit) in StateBit::all().enumerate() {
+            if emu.bit(bit) == Some(SingleRead::Unknown) {
+                vars[i] = count;
+                count += 1;
+            }
+        }
+
+        let mut bdd = Bdd::new();
+        bdd.reserve_vars(count)?;
+
+        Ok(Self {
+            emu: emu.clone(),
+            bdd,
+            vars,
+        })
+    }
+
+    #[must_use]
+    pub const fn emulator(&self) -> &Emulator {
+        &self.emu
+    }
+
+    /// The diagram to build conditions in.
+    pub const fn bdd(&mut self) -> &mut Bdd<NODES> {
+        &mut self.bdd
+    }
+
+    /// The function of a bit, which is constant when the bit is known, or `None` if there is no
+    /// such bit.
+    pub fn bit(&mut self, bit: StateBit) -> Result<Option<BddRef>, BddError> {
+        let (Some(read), Some(i)) = (self.emu.bit(bit), bit.index()) else {
+            return Ok(None);
+        };
+
+        match read.as_bool() {
+            Some(value) => Ok(Some(BddRef::from_bool(value))),
+            None => self.bdd.var(self.vars[i]).map(Some),
+        }
+    }
+
+    /// A refinement of the state in which `f` holds, or `None` if it never can. Bits `f` does not
+    /// depend on stay Unknown.
+    #[must_use]
+    pub fn witness(&self, f: BddRef) -> Option<Emulator> {
+        let mut values = [None; STATE_BIT_COUNT];
+        for (var, value) in self.bdd.any_sat(f)? {
+            // Variables made with `fresh_var` are not state bits.
+            if let Some(slot) = values.get_mut(usize::from(var)) {
+                *slot = Some(value);
+            }
+        }
+
+        let mut emu = self.emu.clone();
+        for (i, bit) in StateBit::all().enumerate() {
+            if let Some(read) = emu.bit_mut(bit)
+                && *read == SingleRead::Unknown
+                && let Some(value) = values[usize::from(self.vars[i])]
+            {
+                *read = SingleRead::from(value);
+            }
+        }
+
+        Some(emu)
+    }
+}
+
+impl Emulator {
+    /// Takes a symbolic view of the state, with room for `NODES` nodes.
+    pub fn symbolic<const NODES: usize>(&self) -> Result<Symbolic<NODES>, BddError> {
+        Symbolic::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_correlated_conditions() {
+        let mut sym = Emulator::new().symbolic::<64>().unwrap();
+        let [x, y] = [0, 1].map(|bit| sym.bit(StateBit::Ram { addr: 5, bit }).unwrap().unwrap());
+
+        let bdd = sym.bdd();
+        assert_eq!(bdd.xor(x, x), Ok(BddRef::FALSE));
+
+        let not_y = bdd.not(y).unwrap();
+        let f = bdd.and(x, not_y).unwrap();
+        let not_x = bdd.not(x).unwrap();
+        let never = bdd.and(f, not_x).unwrap();
+
+        let emu = sym.witness(f).unwrap();
+        assert_eq!(emu.riot.ram()[5][0], SingleRead::High);
+        assert_eq!(emu.riot.ram()[5][1], SingleRead::Low);
+        assert_eq!(emu.riot.ram()[5][2], SingleRead::Unknown);
+        assert!(sym.witness(never).is_none());
+    }
+}
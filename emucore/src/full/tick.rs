@@ -0,0 +1,168 @@
+use crate::{
+    common::{
+        line::error::{ClockEdge, LineError},
+        read::single::SingleRead,
+    },
+    cpu::Cpu,
+    full::{
+        Emulator,
+        bus_hold::BusHoldState,
+        line_reads::{LINE_COUNT, NETS},
+        settle::NetSet,
+    },
+    riot::Riot,
+};
+use strum_macros::Display;
+use thiserror::Error;
+
+/// A stage of [`Emulator::tick`](crate::full::Emulator::tick), in the order they run.
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum TickStage {
+    #[strum(to_string = "CPU rising edge")]
+    CpuRisingEdge,
+
+    #[strum(to_string = "RIOT rising edge")]
+    RiotRisingEdge,
+
+    #[strum(to_string = "CPU falling edge")]
+    CpuFallingEdge,
+
+    #[strum(to_string = "RIOT falling edge")]
+    RiotFallingEdge,
+}
+
+impl TickStage {
+    /// The clock edge the stage handles.
+    #[must_use]
+    pub const fn edge(self) -> ClockEdge {
+        match self {
+            Self::CpuRisingEdge | Self::RiotRisingEdge => ClockEdge::Rising,
+            Self::CpuFallingEdge | Self::RiotFallingEdge => ClockEdge::Falling,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum TickError {
+    #[error("cannot tick as the lines could not be updated before the {stage}: {source}")]
+    Line { stage: TickStage, source: LineError },
+
+    #[error(
+        "cannot tick as nets {nets} did not settle before the {stage} within {iterations} passes"
+    )]
+    Unsettled {
+        stage: TickStage,
+        nets: NetSet,
+        iterations: u32,
+    },
+}
+
+impl TickError {
+    #[must_use]
+    pub const fn stage(&self) -> TickStage {
+        match self {
+            Self::Line { stage, .. } | Self::Unsettled { stage, .. } => *stage,
+        }
+    }
+}
+
+/// What a tick can change before its last stage that can fail, so a failed tick can be undone
+/// without copying the whole emulator.
+///
+/// The drive cache is left as it is, as it only records what lines resolve to from their drives.
+pub(super) struct TickUndo {
+    cpu: Cpu,
+    riot: Riot,
+    lines: [SingleRead; LINE_COUNT],
+    hold: BusHoldState,
+    log: (usize, u64),
+}
+
+impl TickUndo {
+    pub(super) fn new(emu: &Emulator) -> Self {
+        let mut lines = [SingleRead::Unknown; LINE_COUNT];
+        let reads = NETS
+            .iter()
+            .filter_map(|net| emu.line_states.net(net.name))
+            .flatten();
+        for (line, &read) in lines.iter_mut().zip(reads) {
+            *line = read;
+        }
+
+        Self {
+            cpu: emu.cpu.clone(),
+            riot: emu.riot.clone(),
+            lines,
+            hold: emu.line_states.hold.clone(),
+            log: emu.contention.log.mark(),
+        }
+    }
+
+    pub(super) fn restore(self, emu: &mut Emulator) {
+        let mut lines = self.lines.iter();
+        for net in &NETS {
+            if let Some(reads) = emu.line_states.net_mut(net.name) {
+                for (read, &line) in reads.iter_mut().zip(&mut lines) {
+                    *read = line;
+                }
+            }
+        }
+
+        emu.cpu = self.cpu;
+        emu.riot = self.riot;
+        emu.line_states.hold = self.hold;
+        emu.contention.log.truncate(self.log);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{
+            line::{multi::BusDriveState, single::DriveState},
+            signal::LineSignal,
+        },
+        full::{bus_hold::BusHold, contention::ContentionPolicy, ext_drives::ExtDrives},
+    };
+
+    #[test]
+    fn failed_tick_leaves_state() {
+        let mut emu = Emulator::new();
+        emu.set_bus_hold("inp1", Some(BusHold { decay: None }));
+        emu.cpu.a_out = BusDriveState::from_value(0);
+        let before = emu.clone();
+
+        let mut ext = ExtDrives::new();
+        ext.inp1 = BusDriveState::from_signals(&[LineSignal::Low; _]);
+        ext.a = BusDriveState::from_value(1);
+
+        let Err(TickError::Line { stage, source }) = emu.tick(&ext) else {
+            panic!("expected a line error");
+        };
+        assert_eq!(stage, TickStage::CpuRisingEdge);
+        assert!(matches!(source, LineError::ShortCircuit { .. }));
+        assert_eq!(source.context().cycle, Some(0));
+        assert_eq!(source.context().drivers.len(), 2);
+        assert_eq!(emu, before);
+    }
+
+    #[test]
+    fn failed_tick_forgets_logged_shorts() {
+        let mut emu = Emulator::new();
+        emu.set_contention_policy(ContentionPolicy::ResolveAndLog);
+        emu.cpu.a_out = BusDriveState::from_value(0);
+        let before = emu.clone();
+
+        let mut ext = ExtDrives::new();
+        ext.a = BusDriveState::from_value(1);
+        ext.rdiff = DriveState::none_enabled();
+
+        let Err(TickError::Line { source, .. }) = emu.tick(&ext) else {
+            panic!("expected a line error");
+        };
+        assert!(matches!(source, LineError::ImpossibleLineSignal { .. }));
+        assert!(emu.contention_log().is_empty());
+        assert_eq!(emu, before);
+    }
+}
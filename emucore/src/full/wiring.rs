@@ -0,0 +1,543 @@
+use crate::common::{
+    line::{multi::BusDriveState, single::DriveState},
+    read::{multi::MultiRead, single::SingleRead},
+};
+
+/// A component output which drives one or more pins.
+pub trait PinDrives {
+    /// The number of pins the output drives.
+    const PINS: usize;
+
+    /// The drive on the `index`th pin, which single pins ignore.
+    fn pin_drive(&self, index: usize) -> DriveState;
+}
+
+impl PinDrives for DriveState {
+    const PINS: usize = 1;
+
+    fn pin_drive(&self, _index: usize) -> DriveState {
+        *self
+    }
+}
+
+impl<const SIZE: usize> PinDrives for BusDriveState<SIZE> {
+    const PINS: usize = SIZE;
+
+    fn pin_drive(&self, index: usize) -> DriveState {
+        self[index]
+    }
+}
+
+/// A component input which reads one or more pins.
+pub trait PinReads {
+    /// The number of pins the input reads.
+    const PINS: usize;
+
+    fn unknown() -> Self;
+
+    /// Sets the read of the `index`th pin, which single pins ignore.
+    fn set_pin_read(&mut self, index: usize, read: SingleRead);
+}
+
+impl PinReads for SingleRead {
+    const PINS: usize = 1;
+
+    fn unknown() -> Self {
+        Self::Unknown
+    }
+
+    fn set_pin_read(&mut self, _index: usize, read: SingleRead) {
+        *self = read;
+    }
+}
+
+impl<const SIZE: usize> PinReads for MultiRead<SIZE> {
+    const PINS: usize = SIZE;
+
+    fn unknown() -> Self {
+        [SingleRead::Unknown; SIZE].into()
+    }
+
+    fn set_pin_read(&mut self, index: usize, read: SingleRead) {
+        self[index] = read;
+    }
+}
+
+/// The index of the component pin connected to `bit` of a net, for pins starting at `net_bit`
+/// whose first pin has index `first`.
+#[must_use]
+pub const fn pin_index(bit: usize, net_bit: usize, first: usize) -> usize {
+    bit - net_bit + first
+}
+
+/// The number of pins driven by the output `field` selects, so the wiring table can be checked
+/// against it at compile time.
+#[must_use]
+pub const fn drive_pins<D, T: PinDrives>(_field: fn(&D) -> &T) -> usize {
+    T::PINS
+}
+
+/// The number of pins read by the input `field` selects.
+#[must_use]
+pub const fn read_pins<R, T: PinReads>(_field: fn(&mut R) -> &mut T) -> usize {
+    T::PINS
+}
+
+/// Generates the board's nets from a wiring table.
+///
+/// Each net lists the pins attached to it. A pin names its component, its label and, for a group
+/// of numbered pins, the number of the first one after `#`. Buses also give the range of bits the
+/// pins cover. A pin can `drive` the net from a field of [`BoardDrivers`], and `read` it into a
+/// field of [`BoardReads`]; an index after either field gives the pin the first bit maps to.
+///
+/// The table is checked at compile time: no net may have more than [`MAX_LINE_DRIVERS`] drivers,
+/// only pins which can drive or read may do so, and the pins of each field must exist.
+///
+/// From this the macro generates [`BUSES`], [`LINE_NAMES`], [`NETS`], [`EmuLineStates`] with its
+/// contention, reads and codecs, and the [`BoardDrivers`] and [`BoardReads`] structs, along with
+/// a walk over the drives on every line for analyses which follow the wiring.
+///
+/// [`MAX_LINE_DRIVERS`]: crate::common::line::error::MAX_LINE_DRIVERS
+/// [`BoardDrivers`]: crate::full::line_reads::BoardDrivers
+/// [`BoardReads`]: crate::full::line_reads::BoardReads
+/// [`BUSES`]: crate::full::line_reads::BUSES
+/// [`LINE_NAMES`]: crate::full::line_reads::LINE_NAMES
+/// [`NETS`]: crate::full::line_reads::NETS
+/// [`EmuLineStates`]: crate::full::line_reads::EmuLineStates
+macro_rules! board {
+    (@opt) => { None };
+    (@opt $value:literal) => { Some($value) };
+    (@first) => { 0 };
+    (@first $value:literal) => { $value };
+    (@one $_value:tt) => { 1 };
+    (@count $_value:tt $count:expr) => { $count };
+
+    (
+        drivers { $($driver:ident: $driver_ty:ty),+ $(,)? }
+        reads { $($reader:ident: $reads_ty:ident { $($read_field:ident),+ $(,)? }),+ $(,)? }
+        buses {$(
+            $bus:ident[$width:literal] {$(
+                $bcomp:ident $bpin:literal $(# $bfirst:literal)? $bdir:ident [$bstart:literal..$bend:literal]
+                $(drive $bdsrc:ident.$bdfield:ident $([$bdfirst:literal])?)?
+                $(read $brdst:ident.$brfield:ident $([$brfirst:literal])?)?;
+            )*}
+        )+}
+        lines {$(
+            $line:ident {$(
+                $lcomp:ident $lpin:literal $ldir:ident
+                $(drive $ldsrc:ident.$ldfield:ident $([$ldfirst:literal])?)?
+                $(read $lrdst:ident.$lrfield:ident $([$lrfirst:literal])?)?;
+            )*}
+        )+}
+    ) => {
+        $(const _: () = {
+            let drivers = 0 $($(+ $crate::full::wiring::board!(@one $bdsrc))?)*;
+            assert!(
+                drivers <= MAX_LINE_DRIVERS,
+                concat!("the ", stringify!($bus), " bus has more than MAX_LINE_DRIVERS drivers"),
+            );
+        };)+
+        $(const _: () = {
+            let drivers = 0 $($(+ $crate::full::wiring::board!(@one $ldsrc))?)*;
+            assert!(
+                drivers <= MAX_LINE_DRIVERS,
+                concat!("the ", stringify!($line), " line has more than MAX_LINE_DRIVERS drivers"),
+            );
+        };)+
+
+        $($(
+            $(const _: () = {
+                assert!(
+                    PinDirection::$bdir.can_drive(),
+                    concat!("the ", stringify!($bus), " pin ", $bpin, " cannot drive"),
+                );
+                assert!(
+                    $crate::full::wiring::board!(@first $($bdfirst)?) + ($bend - $bstart)
+                        <= $crate::full::wiring::drive_pins(
+                            |d: &BoardDrivers<'static>| &d.$bdsrc.$bdfield,
+                        ),
+                    concat!("the ", stringify!($bus), " pin ", $bpin, " drives missing pins"),
+                );
+            };)?
+            $(const _: () = {
+                assert!(
+                    PinDirection::$bdir.can_read(),
+                    concat!("the ", stringify!($bus), " pin ", $bpin, " cannot read"),
+                );
+                assert!(
+                    $crate::full::wiring::board!(@first $($brfirst)?) + ($bend - $bstart)
+                        <= $crate::full::wiring::read_pins(
+                            |r: &mut BoardReads| &mut r.$brdst.$brfield,
+                        ),
+                    concat!("the ", stringify!($bus), " pin ", $bpin, " reads missing pins"),
+                );
+            };)?
+        )*)+
+        $($(
+            $(const _: () = {
+                assert!(
+                    PinDirection::$ldir.can_drive(),
+                    concat!("the ", stringify!($line), " pin ", $lpin, " cannot drive"),
+                );
+                assert!(
+                    $crate::full::wiring::board!(@first $($ldfirst)?)
+                        < $crate::full::wiring::drive_pins(
+                            |d: &BoardDrivers<'static>| &d.$ldsrc.$ldfield,
+                        ),
+                    concat!("the ", stringify!($line), " pin ", $lpin, " drives a missing pin"),
+                );
+            };)?
+            $(const _: () = {
+                assert!(
+                    PinDirection::$ldir.can_read(),
+                    concat!("the ", stringify!($line), " pin ", $lpin, " cannot read"),
+                );
+                assert!(
+                    $crate::full::wiring::board!(@first $($lrfirst)?)
+                        < $crate::full::wiring::read_pins(
+                            |r: &mut BoardReads| &mut r.$lrdst.$lrfield,
+                        ),
+                    concat!("the ", stringify!($line), " pin ", $lpin, " reads a missing pin"),
+                );
+            };)?
+        )*)+
+
+        /// The position of each net in [`NETS`].
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy)]
+        enum NetPosition {
+            $($bus,)+
+            $($line,)+
+        }
+
+        /// The most drives feeding one net, counting every pin of each driver.
+        pub const MAX_NET_DRIVES: usize = {
+            let counts = [
+                $(0 $($(+ $crate::full::wiring::board!(@count $bdsrc $bend - $bstart))?)*,)+
+                $(0 $($(+ $crate::full::wiring::board!(@count $ldsrc 1))?)*,)+
+            ];
+
+            let mut max = 0;
+            let mut i = 0;
+            while i < counts.len() {
+                if counts[i] > max {
+                    max = counts[i];
+                }
+                i += 1;
+            }
+            max
+        };
+
+        pub const BUSES: [(&str, usize); [$(stringify!($bus)),+].len()] =
+            [$((stringify!($bus), $width)),+];
+        pub const LINE_NAMES: [&str; [$(stringify!($line)),+].len()] = [$(stringify!($line)),+];
+
+        /// Every net on the board, in the order of [`net_index`].
+        pub static NETS: [Net; NET_COUNT] = [
+            $(Net {
+                name: stringify!($bus),
+                width: $width,
+                pins: &[$(Pin {
+                    component: LineDriver::$bcomp,
+                    name: $bpin,
+                    direction: PinDirection::$bdir,
+                    net_bit: $bstart,
+                    first_index: $crate::full::wiring::board!(@opt $($bfirst)?),
+                    width: {
+                        assert!($bstart < $bend && $bend <= $width);
+                        $bend - $bstart
+                    },
+                }),*],
+            },)+
+            $(Net {
+                name: stringify!($line),
+                width: 1,
+                pins: &[$(Pin {
+                    component: LineDriver::$lcomp,
+                    name: $lpin,
+                    direction: PinDirection::$ldir,
+                    net_bit: 0,
+                    first_index: None,
+                    width: 1,
+                }),*],
+            },)+
+        ];
+
+        /// The components which drive the board's lines.
+        #[derive(Clone, Copy, Debug)]
+        pub struct BoardDrivers<'a> {
+            $(pub $driver: &'a $driver_ty,)+
+        }
+
+        impl BoardDrivers<'_> {
+            /// Calls `f` with the drives on every line, each with the component and field it
+            /// comes from.
+            #[cfg(any(test, feature = "untested-analyses"))]
+            pub fn for_each_line(
+                &self,
+                mut f: impl FnMut(LineIdent, &[(LineDriver, &'static str, DriveState)]),
+            ) {
+                $(for bit in 0..$width {
+                    #[allow(unused_mut)]
+                    let mut drives = ArrayVec::<_, MAX_LINE_DRIVERS>::new();
+                    $($(
+                        if ($bstart..$bend).contains(&bit) {
+                            let index = pin_index(
+                                bit,
+                                $bstart,
+                                $crate::full::wiring::board!(@first $($bdfirst)?),
+                            );
+                            drives.push((
+                                LineDriver::$bcomp,
+                                stringify!($bdfield),
+                                self.$bdsrc.$bdfield.pin_drive(index),
+                            ));
+                        }
+                    )?)*
+
+                    f(
+                        LineIdent::BusLine {
+                            bus_name: stringify!($bus),
+                            bit,
+                        },
+                        &drives,
+                    );
+                })+
+
+                $({
+                    #[allow(unused_mut)]
+                    let mut drives = ArrayVec::<_, MAX_LINE_DRIVERS>::new();
+                    $($(
+                        drives.push((
+                            LineDriver::$lcomp,
+                            stringify!($ldfield),
+                            self.$ldsrc.$ldfield.pin_drive(
+                                $crate::full::wiring::board!(@first $($ldfirst)?),
+                            ),
+                        ));
+                    )?)*
+
+                    f(stringify!($line).into(), &drives);
+                })+
+            }
+        }
+
+        /// What each component reads from the board's lines.
+        #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+        pub struct BoardReads {
+            $(pub $reader: $reads_ty,)+
+        }
+
+        #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+        pub struct EmuLineStates {
+            $(pub $bus: MultiRead<$width>,)+
+            $(pub $line: SingleRead,)+
+            pub hold: BusHoldState,
+            pub(crate) cache: DriveCache,
+        }
+
+        impl EmuLineStates {
+            pub fn new() -> Self {
+                Self {
+                    $($bus: PinReads::unknown(),)+
+                    $($line: PinReads::unknown(),)+
+                    hold: BusHoldState::new(),
+                    cache: DriveCache::new(),
+                }
+            }
+
+            #[must_use]
+            pub fn net(&self, name: &str) -> Option<&[SingleRead]> {
+                Some(match name {
+                    $(stringify!($bus) => &self.$bus[..],)+
+                    $(stringify!($line) => slice::from_ref(&self.$line),)+
+                    _ => return None,
+                })
+            }
+
+            pub fn net_mut(&mut self, name: &str) -> Option<&mut [SingleRead]> {
+                Some(match name {
+                    $(stringify!($bus) => &mut self.$bus[..],)+
+                    $(stringify!($line) => slice::from_mut(&mut self.$line),)+
+                    _ => return None,
+                })
+            }
+
+            /// Contends the drivers of every line and reads the result, skipping lines whose
+            /// drivers have not changed since they were last resolved. Returns the nets whose
+            /// value changed.
+            pub fn update(
+                &mut self,
+                drivers: BoardDrivers<'_>,
+                contention: &mut Contention,
+                at: (u64, ClockEdge),
+            ) -> Result<NetSet, LineError> {
+                let changed = [$({
+                    let before = self.$bus.clone();
+                    let net = NetPosition::$bus as usize;
+
+                    let mut seen = NetDrives::new();
+                    $($(
+                        let first = $crate::full::wiring::board!(@first $($bdfirst)?);
+                        for index in first..first + ($bend - $bstart) {
+                            seen.push(drivers.$bdsrc.$bdfield.pin_drive(index).to_bits());
+                        }
+                    )?)*
+
+                    if !self.cache.is_net_clean(&self.hold, net, &seen, &self.$bus[..]) {
+                        let mut cached = true;
+                        for bit in 0..$width {
+                            let ident = LineIdent::BusLine {
+                                bus_name: stringify!($bus),
+                                bit,
+                            };
+
+                            #[allow(unused_mut)]
+                            let mut drives = ArrayVec::<_, MAX_LINE_DRIVERS>::new();
+                            $($(
+                                if ($bstart..$bend).contains(&bit) {
+                                    let index = pin_index(
+                                        bit,
+                                        $bstart,
+                                        $crate::full::wiring::board!(@first $($bdfirst)?),
+                                    );
+                                    drives.push((
+                                        LineDriver::$bcomp,
+                                        drivers.$bdsrc.$bdfield.pin_drive(index),
+                                    ));
+                                }
+                            )?)*
+
+                            if !self.cache.is_clean(&self.hold, ident, &drives, self.$bus[bit]) {
+                                let (read, clean) = resolve_line(
+                                    contention,
+                                    &mut self.hold,
+                                    &mut self.cache,
+                                    ident,
+                                    &drives,
+                                    at,
+                                )?;
+                                self.$bus[bit] = read;
+                                cached &= clean;
+                            }
+                        }
+
+                        self.cache.set_net(net, seen, cached.then_some(&self.$bus[..]));
+                    }
+
+                    self.$bus != before
+                },)+ $({
+                    let before = self.$line;
+                    let net = NetPosition::$line as usize;
+
+                    #[allow(unused_mut)]
+                    let mut drives =
+                        ArrayVec::<(LineDriver, DriveState), MAX_LINE_DRIVERS>::new();
+                    $($(
+                        drives.push((
+                            LineDriver::$lcomp,
+                            drivers.$ldsrc.$ldfield.pin_drive(
+                                $crate::full::wiring::board!(@first $($ldfirst)?),
+                            ),
+                        ));
+                    )?)*
+
+                    let seen: NetDrives =
+                        drives.iter().map(|&(_, drive)| drive.to_bits()).collect();
+                    let current = slice::from_ref(&self.$line);
+                    if !self.cache.is_net_clean(&self.hold, net, &seen, current) {
+                        let (read, cached) = resolve_line(
+                            contention,
+                            &mut self.hold,
+                            &mut self.cache,
+                            stringify!($line).into(),
+                            &drives,
+                            at,
+                        )?;
+                        self.$line = read;
+                        self.cache.set_net(
+                            net,
+                            seen,
+                            cached.then_some(slice::from_ref(&self.$line)),
+                        );
+                    }
+
+                    self.$line != before
+                },)+];
+
+                Ok(NetSet::from_flags(&changed))
+            }
+
+            /// What each component reads from the lines.
+            #[must_use]
+            pub fn reads(&self) -> BoardReads {
+                let mut reads = BoardReads {
+                    $($reader: $reads_ty {
+                        $($read_field: PinReads::unknown(),)+
+                    },)+
+                };
+
+                $(for bit in 0..$width {
+                    $($(
+                        if ($bstart..$bend).contains(&bit) {
+                            let index = pin_index(
+                                bit,
+                                $bstart,
+                                $crate::full::wiring::board!(@first $($brfirst)?),
+                            );
+                            reads.$brdst.$brfield.set_pin_read(index, self.$bus[bit]);
+                        }
+                    )?)*
+                })+
+
+                $($($(
+                    reads.$lrdst.$lrfield.set_pin_read(
+                        $crate::full::wiring::board!(@first $($lrfirst)?),
+                        self.$line,
+                    );
+                )?)*)+
+
+                reads
+            }
+        }
+
+        impl Encode for EmuLineStates {
+            fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+                $(self.$bus.encode(w)?;)+
+                $(self.$line.encode(w)?;)+
+                Ok(())
+            }
+        }
+
+        impl MaxEncodedLen for EmuLineStates {
+            const MAX_ENCODED_LEN: usize =
+                0 $(+ MultiRead::<$width>::MAX_ENCODED_LEN)+
+                    + LINE_NAMES.len() * SingleRead::MAX_ENCODED_LEN;
+        }
+
+        /// Bus-hold state is saved separately, so it starts fresh.
+        impl Decode for EmuLineStates {
+            fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+                Ok(Self {
+                    $($bus: Decode::decode(r)?,)+
+                    $($line: Decode::decode(r)?,)+
+                    hold: BusHoldState::new(),
+                    cache: DriveCache::new(),
+                })
+            }
+        }
+
+        impl Combine for EmuLineStates {
+            fn combine_with(&self, other: &Self) -> Self {
+                Self {
+                    $($bus: self.$bus.combine_with(&other.$bus),)+
+                    $($line: self.$line.combine_with(&other.$line),)+
+                    hold: self.hold.combine_with(&other.hold),
+                    cache: DriveCache::new(),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use board;
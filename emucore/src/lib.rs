@@ -6,6 +6,7 @@
 mod common;
 mod cpu;
 mod full;
+mod movie;
 mod riot;
 
 #[cfg(not(test))]
@@ -14,7 +15,62 @@ const fn panic(_: &core::panic::PanicInfo) -> ! {
     loop {}
 }
 
-pub use crate::full::Emulator;
+pub use crate::{
+    common::{
+        bdd::{Bdd, BddError, BddRef},
+        codec::CodecError,
+        combine::Combine,
+        line::{
+            error::{ClockEdge, DriverState, LineContext, LineDriver, LineError, MAX_LINE_DRIVERS},
+            ident::LineIdent,
+        },
+        read::single::SingleRead,
+    },
+    cpu::regs::CpuReg,
+    full::{
+        Emulator,
+        beam::{COLOR_CLOCKS_PER_CYCLE, COLOR_CLOCKS_PER_SCANLINE, SCANLINES_PER_FRAME},
+        bits::StateBit,
+        bus_hold::{BusHold, BusHoldState},
+        concretize::StateScope,
+        config::EmulatorConfig,
+        console::{ConsoleSwitches, Difficulty, TvType},
+        contention::{
+            CONTENTION_LOG_LEN, Contention, ContentionEvent, ContentionLog, ContentionPolicy,
+        },
+        ext_drives::ExtDrives,
+        fork::{Assumption, Fork, ForkError, Forks, MAX_FORK_BITS},
+        joystick::Joystick,
+        line_reads::NETS,
+        netlist::{Net, Pin, PinAt, PinDirection, net},
+        power_on::{POWER_ON_IMAGE_LEN, PowerOnComponent, PowerOnConfig, PowerOnPolicy},
+        rewind::Rewind,
+        save_state::{SAVE_STATE_MAX_LEN, SAVE_STATE_VERSION, SaveStateError},
+        settle::{DEFAULT_SETTLE_LIMIT, NetSet},
+        source::{DriveSource, UnknownInputs},
+        symbolic::Symbolic,
+        tick::{TickError, TickStage},
+    },
+    movie::{
+        MoviePlayer, MovieRecorder,
+        bk2::{Bk2Error, Bk2Frame, Bk2Import},
+        error::MovieError,
+        header::{MovieHeader, rom_hash},
+    },
+    riot::regs::RiotReg,
+};
+
+/// Analyses which run the emulator through [`Emulator::tick`]. The CPU's clock edge handlers are
+/// not implemented yet, so these panic as soon as the CPU runs and are untested end to end; only
+/// the parts which do not tick are unit tested.
+#[cfg(any(test, feature = "untested-analyses"))]
+pub use crate::full::{
+    determinism::{DIVERGENT_PCS_LEN, FrameUnknowns},
+    fixpoint::{Fixpoint, FixpointError},
+    input_dependence::{DEPENDENT_TIA_WRITES_LEN, DependentTiaWrite, InputDependence},
+    power_on_check::{PowerOnCheck, UnknownSink, UnknownUse},
+    provenance::{Label, LabelSet, Labeled, Provenance},
+};
 
 // pub use crate::{
 //     common::{
@@ -0,0 +1,314 @@
+use crate::full::{
+    console::{ConsoleSwitches, Difficulty},
+    ext_drives::ExtDrives,
+    joystick::Joystick,
+};
+use core::str::Lines;
+use strum_macros::IntoStaticStr;
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Eq, Error, Hash, PartialEq)]
+pub enum Bk2Error {
+    #[error("cannot import input log as it has no LogKey line")]
+    MissingLogKey,
+
+    #[error("cannot import frame {frame} of input log as it does not match the LogKey")]
+    MalformedFrame { frame: usize },
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, IntoStaticStr, PartialEq)]
+enum Button {
+    #[strum(to_string = "Reset")]
+    Reset,
+
+    #[strum(to_string = "Select")]
+    Select,
+
+    #[strum(to_string = "Power")]
+    Power,
+
+    #[strum(to_string = "Toggle Left Difficulty")]
+    ToggleLeftDifficulty,
+
+    #[strum(to_string = "Toggle Right Difficulty")]
+    ToggleRightDifficulty,
+
+    #[strum(to_string = "P1 Up")]
+    P1Up,
+
+    #[strum(to_string = "P1 Down")]
+    P1Down,
+
+    #[strum(to_string = "P1 Left")]
+    P1Left,
+
+    #[strum(to_string = "P1 Right")]
+    P1Right,
+
+    #[strum(to_string = "P1 Button")]
+    P1Fire,
+
+    #[strum(to_string = "P2 Up")]
+    P2Up,
+
+    #[strum(to_string = "P2 Down")]
+    P2Down,
+
+    #[strum(to_string = "P2 Left")]
+    P2Left,
+
+    #[strum(to_string = "P2 Right")]
+    P2Right,
+
+    #[strum(to_string = "P2 Button")]
+    P2Fire,
+}
+
+const BUTTONS: [Button; 15] = [
+    Button::Reset,
+    Button::Select,
+    Button::Power,
+    Button::ToggleLeftDifficulty,
+    Button::ToggleRightDifficulty,
+    Button::P1Up,
+    Button::P1Down,
+    Button::P1Left,
+    Button::P1Right,
+    Button::P1Fire,
+    Button::P2Up,
+    Button::P2Down,
+    Button::P2Left,
+    Button::P2Right,
+    Button::P2Fire,
+];
+
+/// Where each button sits in a frame line, as a `|`-separated group and a column within it.
+struct Bk2Layout {
+    positions: [Option<(usize, usize)>; BUTTONS.len()],
+}
+
+impl Bk2Layout {
+    fn parse(log_key: &str) -> Self {
+        let mut positions = [None; BUTTONS.len()];
+
+        for (group_index, group) in log_key.split('#').skip(1).enumerate() {
+            for (column, name) in group.split('|').filter(|name| !name.is_empty()).enumerate() {
+                if let Some(button) = BUTTONS
+                    .into_iter()
+                    .find(|&button| <&str>::from(button) == name)
+                {
+                    positions[button as usize] = Some((group_index, column));
+                }
+            }
+        }
+
+        Self { positions }
+    }
+
+    fn pressed(&self, line: &str, button: Button) -> Option<bool> {
+        let Some((group_index, column)) = self.positions[button as usize] else {
+            return Some(false);
+        };
+
+        let group = line.split('|').nth(group_index + 1)?;
+        group
+            .as_bytes()
+            .get(column)
+            .map(|&mnemonic| mnemonic != b'.')
+    }
+}
+
+/// The console state for a single frame of an imported movie.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Bk2Frame {
+    pub drives: ExtDrives,
+
+    /// Whether the power button was pressed, which the caller handles by power-cycling.
+    pub power: bool,
+}
+
+/// Imports the `Input Log.txt` entry of a `BizHawk` `.bk2` archive for the Atari 2600 core.
+///
+/// Extracting the entry from the archive is left to the caller. Each frame line becomes one
+/// [`Bk2Frame`], which should be applied for a whole frame starting at the frame boundary.
+pub struct Bk2Import<'a> {
+    lines: Lines<'a>,
+    layout: Bk2Layout,
+    switches: ConsoleSwitches,
+    left_toggle_held: bool,
+    right_toggle_held: bool,
+    frame: usize,
+    /// Whether the end of the input block has been reached.
+    done: bool,
+}
+
+impl<'a> Bk2Import<'a> {
+    /// Starts an import, with `switches` giving the panel state before the first frame.
+    pub fn new(input_log: &'a str, switches: ConsoleSwitches) -> Result<Self, Bk2Error> {
+        let mut lines = input_log.lines();
+        let log_key = lines
+            .by_ref()
+            .find_map(|line| line.trim().strip_prefix("LogKey:"))
+            .ok_or(Bk2Error::MissingLogKey)?;
+
+        Ok(Self {
+            lines,
+            layout: Bk2Layout::parse(log_key),
+            switches,
+            left_toggle_held: false,
+            right_toggle_held: false,
+            frame: 0,
+            done: false,
+        })
+    }
+
+    const fn toggle(difficulty: Difficulty) -> Difficulty {
+        match difficulty {
+            Difficulty::A => Difficulty::B,
+            Difficulty::B => Difficulty::A,
+        }
+    }
+
+    fn parse_frame(&mut self, line: &str) -> Result<Bk2Frame, Bk2Error> {
+        let frame = self.frame;
+        let pressed = |button| {
+            self.layout
+                .pressed(line, button)
+                .ok_or(Bk2Error::MalformedFrame { frame })
+        };
+
+        let p1 = Joystick {
+            up: pressed(Button::P1Up)?,
+            down: pressed(Button::P1Down)?,
+            left: pressed(Button::P1Left)?,
+            right: pressed(Button::P1Right)?,
+            fire: pressed(Button::P1Fire)?,
+        };
+
+        let p2 = Joystick {
+            up: pressed(Button::P2Up)?,
+            down: pressed(Button::P2Down)?,
+            left: pressed(Button::P2Left)?,
+            right: pressed(Button::P2Right)?,
+            fire: pressed(Button::P2Fire)?,
+        };
+
+        let reset = pressed(Button::Reset)?;
+        let select = pressed(Button::Select)?;
+        let power = pressed(Button::Power)?;
+        let left_toggle = pressed(Button::ToggleLeftDifficulty)?;
+        let right_toggle = pressed(Button::ToggleRightDifficulty)?;
+
+        // BizHawk flips a difficulty switch when its toggle is first pressed, not while held
+        if left_toggle && !self.left_toggle_held {
+            self.switches.left_difficulty = Self::toggle(self.switches.left_difficulty);
+        }
+        if right_toggle && !self.right_toggle_held {
+            self.switches.right_difficulty = Self::toggle(self.switches.right_difficulty);
+        }
+        self.left_toggle_held = left_toggle;
+        self.right_toggle_held = right_toggle;
+
+        if reset {
+            self.switches.press_reset(1);
+        }
+        if select {
+            self.switches.press_select(1);
+        }
+
+        let mut drives = ExtDrives::new();
+        p1.drive(&mut drives.inp1);
+        p2.drive(&mut drives.inp2);
+        self.switches.drive(&mut drives);
+        self.switches.end_frame();
+
+        Ok(Bk2Frame { drives, power })
+    }
+}
+
+impl Iterator for Bk2Import<'_> {
+    type Item = Result<Bk2Frame, Bk2Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        while let Some(line) = self.lines.next().map(str::trim) {
+            if line == "[/Input]" {
+                break;
+            }
+
+            if line.starts_with('|') {
+                let res = self.parse_frame(line);
+                self.frame += 1;
+                return Some(res);
+            }
+        }
+
+        self.done = true;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{line::single::DriveState, signal::LineSignal};
+
+    const LOG: &str = "[Input]
+LogKey:#Reset|Select|Power|Toggle Left Difficulty|Toggle Right Difficulty|#P1 Up|P1 Down|P1 Left|P1 Right|P1 Button|#P2 Up|P2 Down|P2 Left|P2 Right|P2 Button|
+|.....|.....|.....|
+|r....|U...B|.....|
+|...l.|.....|..L..|
+|...l.|.....|.....|
+[/Input]
+|r....|.....|.....|
+";
+
+    #[test]
+    fn imports_frames() {
+        let frames: [_; 4] = Bk2Import::new(LOG, ConsoleSwitches::new())
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<arrayvec::ArrayVec<_, 4>>()
+            .into_inner()
+            .unwrap();
+
+        let open = DriveState::from(LineSignal::HighZ);
+        assert_eq!(frames[0].drives.res, open);
+        assert_eq!(frames[1].drives.res, DriveState::from(false));
+        assert_eq!(frames[1].drives.inp1[0], DriveState::from(false));
+        assert_eq!(frames[1].drives.inp1[5], DriveState::from(false));
+        assert_eq!(frames[1].drives.inp2[2], open);
+        assert_eq!(frames[2].drives.inp2[2], DriveState::from(false));
+        assert_eq!(frames[2].drives.ldiff, open);
+        assert_eq!(frames[3].drives.ldiff, open);
+        assert!(frames.iter().all(|frame| !frame.power));
+    }
+
+    #[test]
+    fn stops_at_end_of_input() {
+        let mut import = Bk2Import::new(LOG, ConsoleSwitches::new()).unwrap();
+        assert_eq!(import.by_ref().count(), 4);
+        assert!(import.next().is_none());
+    }
+
+    #[test]
+    fn rejects_short_frame() {
+        let log = "LogKey:#Reset|Select|#P1 Up|\n|r|\n";
+        let mut import = Bk2Import::new(log, ConsoleSwitches::new()).unwrap();
+        assert_eq!(
+            import.next(),
+            Some(Err(Bk2Error::MalformedFrame { frame: 0 }))
+        );
+    }
+
+    #[test]
+    fn missing_log_key() {
+        assert!(matches!(
+            Bk2Import::new("[Input]\n|.....|\n", ConsoleSwitches::new()),
+            Err(Bk2Error::MissingLogKey)
+        ));
+    }
+}
@@ -0,0 +1,20 @@
+use crate::{common::codec::CodecError, full::power_on::PowerOnComponent};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Eq, Error, Hash, PartialEq)]
+pub enum MovieError {
+    #[error("cannot process movie data: {0}")]
+    Codec(#[from] CodecError),
+
+    #[error("cannot load movie as it does not start with the movie file signature")]
+    BadMagic,
+
+    #[error("cannot load movie as format version {version} is not supported")]
+    UnsupportedVersion { version: u16 },
+
+    #[error("cannot play movie recorded for ROM {expected:#018x} on ROM {found:#018x}")]
+    RomMismatch { expected: u64, found: u64 },
+
+    #[error("cannot play movie as it was recorded with a different {component} power-on policy")]
+    PowerOnMismatch { component: PowerOnComponent },
+}
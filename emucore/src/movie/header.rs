@@ -0,0 +1,81 @@
+use crate::{
+    common::codec::{CodecError, Decode, Encode, Reader, Writer},
+    full::power_on::{PowerOnConfig, PowerOnPolicy},
+    movie::error::MovieError,
+};
+
+const MAGIC: [u8; 4] = *b"E26M";
+const VERSION: u16 = 2;
+
+/// The first version, which stored a single power-on policy for the whole console.
+const VERSION_UNIFORM_POWER_ON: u16 = 1;
+
+/// Hashes a ROM image with 64-bit FNV-1a, for identifying the ROM a movie was recorded on.
+#[must_use]
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    rom.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MovieHeader {
+    pub rom_hash: u64,
+    pub power_on: PowerOnConfig,
+}
+
+impl MovieHeader {
+    pub fn write(&self, w: &mut Writer<'_>) -> Result<(), MovieError> {
+        w.write_bytes(&MAGIC)?;
+        w.write_u16(VERSION)?;
+        self.encode(w)?;
+        Ok(())
+    }
+
+    pub fn read(r: &mut Reader<'_>) -> Result<Self, MovieError> {
+        if r.read_bytes()? != MAGIC {
+            return Err(MovieError::BadMagic);
+        }
+
+        match r.read_u16()? {
+            VERSION => Ok(Self::decode(r)?),
+            VERSION_UNIFORM_POWER_ON => Ok(Self {
+                rom_hash: r.read_u64()?,
+                power_on: PowerOnConfig::uniform(PowerOnPolicy::decode(r)?),
+            }),
+            version => Err(MovieError::UnsupportedVersion { version }),
+        }
+    }
+
+    /// Checks that a movie with this header can be played back in the `current` setup.
+    pub fn check(&self, current: &Self) -> Result<(), MovieError> {
+        if self.rom_hash != current.rom_hash {
+            return Err(MovieError::RomMismatch {
+                expected: self.rom_hash,
+                found: current.rom_hash,
+            });
+        }
+
+        if let Some(component) = self.power_on.mismatch(&current.power_on) {
+            return Err(MovieError::PowerOnMismatch { component });
+        }
+
+        Ok(())
+    }
+}
+
+impl Encode for MovieHeader {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        w.write_u64(self.rom_hash)?;
+        self.power_on.encode(w)
+    }
+}
+
+impl Decode for MovieHeader {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        Ok(Self {
+            rom_hash: r.read_u64()?,
+            power_on: PowerOnConfig::decode(r)?,
+        })
+    }
+}
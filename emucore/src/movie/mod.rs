@@ -0,0 +1,247 @@
+pub mod bk2;
+pub mod error;
+pub mod header;
+
+use crate::{
+    common::codec::{Decode, Encode, Reader, Writer},
+    full::ext_drives::ExtDrives,
+    movie::{error::MovieError, header::MovieHeader},
+};
+
+/// Records the external drives of a session into a movie, one call per tick.
+///
+/// Only ticks where the drives change are stored, each as the number of ticks since the previous
+/// change followed by the new drives. Console switches are captured through the drives they place
+/// on the PB lines.
+pub struct MovieRecorder<'a> {
+    writer: Writer<'a>,
+    last: Option<ExtDrives>,
+    last_change: u64,
+    tick: u64,
+}
+
+impl<'a> MovieRecorder<'a> {
+    pub fn new(buf: &'a mut [u8], header: &MovieHeader) -> Result<Self, MovieError> {
+        let mut writer = Writer::new(buf);
+        header.write(&mut writer)?;
+
+        Ok(Self {
+            writer,
+            last: None,
+            last_change: 0,
+            tick: 0,
+        })
+    }
+
+    #[must_use]
+    pub const fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Records the drives for the next tick. If the buffer is full, nothing is recorded and the
+    /// movie written so far stays valid.
+    pub fn record(&mut self, drives: &ExtDrives) -> Result<(), MovieError> {
+        if self.last.as_ref() != Some(drives) {
+            let start = self.writer.position();
+            self.writer
+                .write_varint(self.tick - self.last_change)
+                .and_then(|()| drives.encode(&mut self.writer))
+                .inspect_err(|_| self.writer.truncate(start))?;
+            self.last = Some(drives.clone());
+            self.last_change = self.tick;
+        }
+
+        self.tick += 1;
+        Ok(())
+    }
+
+    /// Records the same drives for `ticks` consecutive ticks, such as a whole imported frame.
+    pub fn record_for(&mut self, drives: &ExtDrives, ticks: u64) -> Result<(), MovieError> {
+        if ticks > 0 {
+            self.record(drives)?;
+            self.tick += ticks - 1;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the length of the recorded movie in bytes.
+    #[must_use]
+    pub const fn finish(self) -> usize {
+        self.writer.position()
+    }
+}
+
+/// Plays back a movie recorded by [`MovieRecorder`], one call per tick.
+pub struct MoviePlayer<'a> {
+    reader: Reader<'a>,
+    header: MovieHeader,
+    drives: ExtDrives,
+    next_change: Option<u64>,
+    tick: u64,
+}
+
+impl<'a> MoviePlayer<'a> {
+    /// Loads a movie, rejecting it unless it was recorded in the `current` setup.
+    pub fn new(data: &'a [u8], current: &MovieHeader) -> Result<Self, MovieError> {
+        let mut reader = Reader::new(data);
+        let header = MovieHeader::read(&mut reader)?;
+        header.check(current)?;
+
+        let next_change = if reader.is_empty() {
+            None
+        } else {
+            Some(reader.read_varint()?)
+        };
+
+        Ok(Self {
+            reader,
+            header,
+            drives: ExtDrives::new(),
+            next_change,
+            tick: 0,
+        })
+    }
+
+    #[must_use]
+    pub const fn header(&self) -> &MovieHeader {
+        &self.header
+    }
+
+    #[must_use]
+    pub const fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    #[must_use]
+    pub const fn is_finished(&self) -> bool {
+        self.next_change.is_none()
+    }
+
+    /// Returns the drives for the current tick, then moves on to the next tick.
+    ///
+    /// Once every recorded change has been played, the last drives are held indefinitely.
+    pub fn next_drives(&mut self) -> Result<&ExtDrives, MovieError> {
+        if self.next_change == Some(self.tick) {
+            self.drives = ExtDrives::decode(&mut self.reader)?;
+
+            self.next_change = if self.reader.is_empty() {
+                None
+            } else {
+                Some(self.tick + self.reader.read_varint()?)
+            };
+        }
+
+        self.tick += 1;
+        Ok(&self.drives)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::line::single::DriveState,
+        full::{
+            console::ConsoleSwitches,
+            power_on::{PowerOnComponent, PowerOnConfig, PowerOnPolicy},
+        },
+    };
+
+    const HEADER: MovieHeader = MovieHeader {
+        rom_hash: 0x1234,
+        power_on: PowerOnConfig::new(),
+    };
+
+    fn session() -> impl Iterator<Item = ExtDrives> {
+        let mut switches = ConsoleSwitches::new();
+        let mut drives = ExtDrives::new();
+
+        (0..40).map(move |tick| {
+            if tick == 10 {
+                switches.press_reset(1);
+            } else if tick == 20 {
+                switches.end_frame();
+            }
+
+            drives.inp1[0] = DriveState::from(tick % 7 == 0);
+            switches.drive(&mut drives);
+            drives.clone()
+        })
+    }
+
+    #[test]
+    fn replay_matches_recording() {
+        let mut buf = [0; 1024];
+        let mut recorder = MovieRecorder::new(&mut buf, &HEADER).unwrap();
+        for drives in session() {
+            recorder.record(&drives).unwrap();
+        }
+        let len = recorder.finish();
+
+        let mut player = MoviePlayer::new(&buf[..len], &HEADER).unwrap();
+        for drives in session() {
+            assert_eq!(player.next_drives().unwrap(), &drives);
+        }
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn full_buffer_keeps_movie_valid() {
+        let mut buf = [0; 48];
+        let mut recorder = MovieRecorder::new(&mut buf, &HEADER).unwrap();
+        let mut kept = 0;
+        let mut len = recorder.writer.position();
+        for drives in session() {
+            if recorder.record(&drives).is_err() {
+                break;
+            }
+            kept += 1;
+            len = recorder.writer.position();
+        }
+        assert!(kept < session().count());
+        assert_eq!(recorder.finish(), len);
+
+        let mut player = MoviePlayer::new(&buf[..len], &HEADER).unwrap();
+        for drives in session().take(kept) {
+            assert_eq!(player.next_drives().unwrap(), &drives);
+        }
+    }
+
+    #[test]
+    fn rejects_other_rom() {
+        let mut buf = [0; 64];
+        let len = MovieRecorder::new(&mut buf, &HEADER).unwrap().finish();
+
+        let other = MovieHeader {
+            rom_hash: 0x4321,
+            ..HEADER
+        };
+
+        assert!(matches!(
+            MoviePlayer::new(&buf[..len], &other),
+            Err(MovieError::RomMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_other_power_on() {
+        let mut buf = [0; 64];
+        let len = MovieRecorder::new(&mut buf, &HEADER).unwrap().finish();
+
+        let other = MovieHeader {
+            power_on: PowerOnConfig {
+                ram: PowerOnPolicy::Zero,
+                ..HEADER.power_on
+            },
+            ..HEADER
+        };
+
+        assert_eq!(
+            MoviePlayer::new(&buf[..len], &other).err(),
+            Some(MovieError::PowerOnMismatch {
+                component: PowerOnComponent::Ram
+            })
+        );
+    }
+}
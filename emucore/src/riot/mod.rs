@@ -3,6 +3,7 @@
 
 use crate::{
     common::{
+        codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer, max_encoded_len},
         combine::{Combine, mux_matches},
         cond::{IsCondition, base::BaseCondition, check::CheckIs},
         line::{multi::BusDriveState, single::DriveState},
@@ -18,7 +19,7 @@
 use core::array;
 use itertools::izip;
 
-const RAM_SIZE: usize = 128;
+pub const RAM_SIZE: usize = 128;
 const PB_CONNECTED_LINES: [u8; 5] = [0, 1, 3, 6, 7];
 const TIMER_INTERVALS: [u16; 4] = [1, 8, 64, 1024];
 
@@ -40,14 +41,40 @@ impl Riot {
     pub fn new() -> Self {
         Self {
             db_out: BusDriveState::from_signals(&[LineSignal::HighZ; _]),
-            pa_out: BusDriveState::from_signals(&[LineSignal::HighZ; _]),
-            pb_out: BusDriveState::from_signals(&[LineSignal::HighZ; _]),
+            pa_out: BusDriveState::from_signals(&[LineSignal::WeakHigh; _]),
+            pb_out: BusDriveState::from_signals(&[LineSignal::WeakHigh; _]),
             reg: RiotRegs::new(),
             ram: array::from_fn(|_| [BitReg::Unknown; _].into()),
             old_pa7_read: SingleRead::Unknown,
         }
     }
 
+    #[must_use]
+    pub const fn regs(&self) -> &RiotRegs {
+        &self.reg
+    }
+
+    pub const fn regs_mut(&mut self) -> &mut RiotRegs {
+        &mut self.reg
+    }
+
+    #[must_use]
+    pub const fn ram(&self) -> &[MBitReg<8>; RAM_SIZE] {
+        &self.ram
+    }
+
+    /// Every output drive, in the order data bus, port A, then port B.
+    pub fn drives_mut(&mut self) -> impl Iterator<Item = &mut DriveState> {
+        self.db_out
+            .iter_mut()
+            .chain(self.pa_out.iter_mut())
+            .chain(self.pb_out.iter_mut())
+    }
+
+    pub const fn ram_mut(&mut self) -> &mut [MBitReg<8>; RAM_SIZE] {
+        &mut self.ram
+    }
+
     fn update_edc(&mut self, r: &RiotAllReads, pa7_read: SingleRead) {
         let edge_type = r.reg.edc_edge_type.as_cond();
         let old_pa7 = self.old_pa7_read.as_cond();
@@ -233,13 +260,14 @@ fn update_db_bus(&mut self, r: &RiotAllReads) {
         });
     }
 
+    /// Drives the port pins set as outputs, leaving the others to their internal pull-ups.
     fn update_peripherals(&mut self, r: &RiotAllReads) {
         for (pa_line, &ddra_bit, &ora_bit) in
             izip!(self.pa_out.iter_mut(), r.reg.ddra.iter(), r.reg.ora.iter())
         {
             *pa_line = Combine::mux(
                 ddra_bit.as_cond(),
-                &|| DriveState::from(LineSignal::HighZ),
+                &|| DriveState::from(LineSignal::WeakHigh),
                 &|| DriveState::from(ora_bit),
             );
         }
@@ -247,7 +275,7 @@ fn update_peripherals(&mut self, r: &RiotAllReads) {
         for (pb_out_state, &pb_con_index) in self.pb_out.iter_mut().zip(PB_CONNECTED_LINES.iter()) {
             *pb_out_state = Combine::mux(
                 r.reg.ddrb[usize::from(pb_con_index)].as_cond(),
-                &|| DriveState::from(LineSignal::HighZ),
+                &|| DriveState::from(LineSignal::WeakHigh),
                 &|| DriveState::from(r.reg.orb[usize::from(pb_con_index)]),
             );
         }
@@ -276,7 +304,72 @@ pub fn handle_rising_edge(&mut self, line_reads: RiotLineReads) {
         self.old_pa7_read = new_pa7_read;
     }
 
+    /// Recomputes the data bus drive from the lines, as the RIOT decodes reads without waiting
+    /// for a clock edge.
+    pub fn update_db_out(&mut self, line_reads: RiotLineReads) {
+        let r = RiotAllReads::new(line_reads, self.reg.clone());
+        self.update_db_bus(&r);
+    }
+
     pub fn handle_falling_edge(&mut self) {
         self.db_out = BusDriveState::from_signals(&[LineSignal::HighZ; 8]);
     }
 }
+
+impl Encode for Riot {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        self.db_out.encode(w)?;
+        self.pa_out.encode(w)?;
+        self.pb_out.encode(w)?;
+        self.reg.encode(w)?;
+
+        for byte in &self.ram {
+            byte.encode(w)?;
+        }
+
+        self.old_pa7_read.encode(w)
+    }
+}
+
+impl MaxEncodedLen for Riot {
+    const MAX_ENCODED_LEN: usize = max_encoded_len!(
+        BusDriveState<8>,
+        BusDriveState<8>,
+        BusDriveState<5>,
+        RiotRegs,
+        SingleRead
+    ) + RAM_SIZE * MBitReg::<8>::MAX_ENCODED_LEN;
+}
+
+impl Decode for Riot {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        Ok(Self {
+            db_out: Decode::decode(r)?,
+            pa_out: Decode::decode(r)?,
+            pb_out: Decode::decode(r)?,
+            reg: Decode::decode(r)?,
+            ram: {
+                let mut ram: [MBitReg<8>; RAM_SIZE] =
+                    array::from_fn(|_| [BitReg::Unknown; _].into());
+                for byte in &mut ram {
+                    *byte = Decode::decode(r)?;
+                }
+                ram
+            },
+            old_pa7_read: Decode::decode(r)?,
+        })
+    }
+}
+
+impl Combine for Riot {
+    fn combine_with(&self, other: &Self) -> Self {
+        Self {
+            db_out: self.db_out.combine_with(&other.db_out),
+            pa_out: self.pa_out.combine_with(&other.pa_out),
+            pb_out: self.pb_out.combine_with(&other.pb_out),
+            reg: self.reg.combine_with(&other.reg),
+            ram: array::from_fn(|addr| self.ram[addr].combine_with(&other.ram[addr])),
+            old_pa7_read: self.old_pa7_read.combine_with(&other.old_pa7_read),
+        }
+    }
+}
@@ -1,4 +1,68 @@
-use crate::common::reg::{BitReg, MBitReg};
+use crate::common::{
+    codec::{CodecError, Decode, Encode, MaxEncodedLen, Reader, Writer, max_encoded_len},
+    combine::Combine,
+    reg::{BitReg, MBitReg},
+};
+use core::slice;
+use strum_macros::Display;
+
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum RiotReg {
+    #[strum(to_string = "DDRA")]
+    Ddra,
+
+    #[strum(to_string = "DDRB")]
+    Ddrb,
+
+    #[strum(to_string = "ORA")]
+    Ora,
+
+    #[strum(to_string = "ORB")]
+    Orb,
+
+    #[strum(to_string = "edge detect interrupt flag")]
+    EdcIrFlag,
+
+    #[strum(to_string = "timer interrupt flag")]
+    TimerIrFlag,
+
+    #[strum(to_string = "edge detect edge type")]
+    EdcEdgeType,
+
+    #[strum(to_string = "timer")]
+    Timer,
+
+    #[strum(to_string = "sub-timer")]
+    SubTimer,
+
+    #[strum(to_string = "timer interval")]
+    TimerInterval,
+}
+
+impl RiotReg {
+    pub const ALL: [Self; 10] = [
+        Self::Ddra,
+        Self::Ddrb,
+        Self::Ora,
+        Self::Orb,
+        Self::EdcIrFlag,
+        Self::TimerIrFlag,
+        Self::EdcEdgeType,
+        Self::Timer,
+        Self::SubTimer,
+        Self::TimerInterval,
+    ];
+
+    #[must_use]
+    pub const fn width(self) -> usize {
+        match self {
+            Self::Ddra | Self::Ddrb | Self::Ora | Self::Orb | Self::Timer => 8,
+            Self::SubTimer => 10,
+            Self::TimerInterval => 2,
+            Self::EdcIrFlag | Self::TimerIrFlag | Self::EdcEdgeType => 1,
+        }
+    }
+}
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct RiotRegs {
@@ -35,4 +99,99 @@ pub fn new() -> Self {
             timer_interval: [BitReg::Unknown; _].into(),
         }
     }
+
+    #[must_use]
+    pub fn bits(&self, reg: RiotReg) -> &[BitReg] {
+        match reg {
+            RiotReg::Ddra => &self.ddra[..],
+            RiotReg::Ddrb => &self.ddrb[..],
+            RiotReg::Ora => &self.ora[..],
+            RiotReg::Orb => &self.orb[..],
+            RiotReg::EdcIrFlag => slice::from_ref(&self.edc_ir_flag),
+            RiotReg::TimerIrFlag => slice::from_ref(&self.timer_ir_flag),
+            RiotReg::EdcEdgeType => slice::from_ref(&self.edc_edge_type),
+            RiotReg::Timer => &self.timer[..],
+            RiotReg::SubTimer => &self.sub_timer[..],
+            RiotReg::TimerInterval => &self.timer_interval[..],
+        }
+    }
+
+    pub fn bits_mut(&mut self, reg: RiotReg) -> &mut [BitReg] {
+        match reg {
+            RiotReg::Ddra => &mut self.ddra[..],
+            RiotReg::Ddrb => &mut self.ddrb[..],
+            RiotReg::Ora => &mut self.ora[..],
+            RiotReg::Orb => &mut self.orb[..],
+            RiotReg::EdcIrFlag => slice::from_mut(&mut self.edc_ir_flag),
+            RiotReg::TimerIrFlag => slice::from_mut(&mut self.timer_ir_flag),
+            RiotReg::EdcEdgeType => slice::from_mut(&mut self.edc_edge_type),
+            RiotReg::Timer => &mut self.timer[..],
+            RiotReg::SubTimer => &mut self.sub_timer[..],
+            RiotReg::TimerInterval => &mut self.timer_interval[..],
+        }
+    }
+}
+
+impl Encode for RiotRegs {
+    fn encode(&self, w: &mut Writer<'_>) -> Result<(), CodecError> {
+        self.ddra.encode(w)?;
+        self.ddrb.encode(w)?;
+        self.ora.encode(w)?;
+        self.orb.encode(w)?;
+        self.edc_ir_flag.encode(w)?;
+        self.timer_ir_flag.encode(w)?;
+        self.edc_edge_type.encode(w)?;
+        self.timer.encode(w)?;
+        self.sub_timer.encode(w)?;
+        self.timer_interval.encode(w)
+    }
+}
+
+impl MaxEncodedLen for RiotRegs {
+    const MAX_ENCODED_LEN: usize = max_encoded_len!(
+        MBitReg<8>,
+        MBitReg<8>,
+        MBitReg<8>,
+        MBitReg<8>,
+        BitReg,
+        BitReg,
+        BitReg,
+        MBitReg<8>,
+        MBitReg<10>,
+        MBitReg<2>,
+    );
+}
+
+impl Decode for RiotRegs {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, CodecError> {
+        Ok(Self {
+            ddra: Decode::decode(r)?,
+            ddrb: Decode::decode(r)?,
+            ora: Decode::decode(r)?,
+            orb: Decode::decode(r)?,
+            edc_ir_flag: Decode::decode(r)?,
+            timer_ir_flag: Decode::decode(r)?,
+            edc_edge_type: Decode::decode(r)?,
+            timer: Decode::decode(r)?,
+            sub_timer: Decode::decode(r)?,
+            timer_interval: Decode::decode(r)?,
+        })
+    }
+}
+
+impl Combine for RiotRegs {
+    fn combine_with(&self, other: &Self) -> Self {
+        Self {
+            ddra: self.ddra.combine_with(&other.ddra),
+            ddrb: self.ddrb.combine_with(&other.ddrb),
+            ora: self.ora.combine_with(&other.ora),
+            orb: self.orb.combine_with(&other.orb),
+            edc_ir_flag: self.edc_ir_flag.combine_with(&other.edc_ir_flag),
+            timer_ir_flag: self.timer_ir_flag.combine_with(&other.timer_ir_flag),
+            edc_edge_type: self.edc_edge_type.combine_with(&other.edc_edge_type),
+            timer: self.timer.combine_with(&other.timer),
+            sub_timer: self.sub_timer.combine_with(&other.sub_timer),
+            timer_interval: self.timer_interval.combine_with(&other.timer_interval),
+        }
+    }
 }